@@ -0,0 +1,24 @@
+#!/usr/bin/env python3
+"""Extracts the positions and elevations of the airports known to the library from the
+`airports.csv` file of OurAirports (https://ourairports.com/data/).
+
+e.g. python3 data/geodata/extract_airports.py airports.csv > src/types/airport_positions.csv
+"""
+
+import csv
+import os
+import sys
+
+ROOT = os.path.join(os.path.dirname(__file__), "..", "..")
+
+with open(os.path.join(ROOT, "src", "types", "airports.csv"), encoding="utf-8") as file:
+    known = {row["oaci"] for row in csv.DictReader(file, delimiter=";")}
+
+with open(sys.argv[1], encoding="utf-8") as file:
+    rows = [row for row in csv.DictReader(file) if row["ident"] in known]
+
+writer = csv.writer(sys.stdout, lineterminator="\n")
+writer.writerow(["ident", "latitude_deg", "longitude_deg", "elevation_ft"])
+
+for row in sorted(rows, key=lambda row: row["ident"]):
+    writer.writerow([row[column] for column in ["ident", "latitude_deg", "longitude_deg", "elevation_ft"]])
@@ -0,0 +1,37 @@
+//! Local stand-in for Meteo France's `serveur_donnees.jsp`, serving the fixtures of a directory.
+//!
+//! Usage: `aeroweb-mock-server [--address 127.0.0.1:8080] [--fixtures ./data] [--api-key KEY]
+//! [--delay-ms 0]`
+
+use aeroweb::mock_server::MockServer;
+use aeroweb::transport::MemoryTransport;
+use std::time::Duration;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut address = String::from("127.0.0.1:8080");
+    let mut fixtures = String::from("./data");
+    let mut api_key = String::from("api_key");
+    let mut delay = 0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("Missing value for {arg}"))?;
+
+        match arg.as_str() {
+            "--address" => address = value,
+            "--fixtures" => fixtures = value,
+            "--api-key" => api_key = value,
+            "--delay-ms" => delay = value.parse()?,
+            _ => return Err(format!("Unknown argument {arg}").into()),
+        }
+    }
+
+    let server = MockServer::bind(address, MemoryTransport::from_dir(&fixtures)?, &api_key).await?;
+    server.set_delay(Duration::from_millis(delay));
+
+    println!("Serving {fixtures} on {}", server.url());
+    server.wait().await;
+
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use crate::airport::Airport;
+use crate::error::Error;
+use crate::geodata::Geodata;
+use crate::quota::Usage;
+use crate::transport::Transport;
+use std::path::PathBuf;
+
+/// Blocking version of `aeroweb::client::Client`, running the asynchronous client on its own
+/// runtime. It must not be used from within an asynchronous runtime.
+#[derive(Debug)]
+pub struct Client {
+    /// Asynchronous client sending the requests, holding the cache and rate limit settings
+    pub inner: crate::client::Client,
+
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Client {
+    /// # Panics
+    ///
+    /// Panics if the runtime cannot be started.
+    ///
+    #[must_use]
+    pub fn new(api_key: &str) -> Client {
+        Client::with_client(crate::client::Client::new(api_key))
+    }
+
+    /// Creates a client recording every response of the API as a cassette in `directory`, with
+    /// the API key scrubbed from the requests, to be served later by a client in replay mode
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime cannot be started.
+    ///
+    #[must_use]
+    pub fn recording(api_key: &str, directory: impl Into<PathBuf>) -> Client {
+        Client::with_client(crate::client::Client::recording(api_key, directory))
+    }
+
+    /// Creates a client serving the cassettes recorded in `directory` instead of querying the API
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime cannot be started.
+    ///
+    #[must_use]
+    pub fn replaying(directory: impl Into<PathBuf>) -> Client {
+        Client::with_client(crate::client::Client::replaying(directory))
+    }
+
+    /// Creates a client sending its requests through a custom transport
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime cannot be started.
+    ///
+    #[must_use]
+    pub fn with_transport(transport: impl Transport + 'static) -> Client {
+        Client::with_client(crate::client::Client::with_transport(transport))
+    }
+
+    /// Wraps an asynchronous client
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime cannot be started.
+    ///
+    #[must_use]
+    pub fn with_client(inner: crate::client::Client) -> Client {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Unable to start the runtime");
+
+        Client { inner, runtime }
+    }
+
+    /// Returns the number of requests sent to the API, per product and per day
+    #[must_use]
+    pub fn usage(&self) -> Usage {
+        self.inner.usage()
+    }
+
+    /// Retrieves the weather along a route
+    /// See `aeroweb::client::Client::get_briefing`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the route has less than 2 waypoints.
+    ///
+    pub fn get_briefing(
+        &self,
+        options: crate::briefing::RequestOptions,
+        geodata: &Geodata,
+    ) -> Result<crate::briefing::Briefing, Error> {
+        self.runtime
+            .block_on(self.inner.get_briefing(options, geodata))
+    }
+
+    /// Retrieves pre-established flight plans
+    /// See `aeroweb::client::Client::get_fligh_plan`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_fligh_plan(
+        &self,
+        options: crate::flight_plan::RequestOptions,
+    ) -> Result<crate::flight_plan::FlightPlan, Error> {
+        self.runtime.block_on(self.inner.get_fligh_plan(options))
+    }
+
+    /// Retrieves MAAs (Messages d'Avertissement d'Aérodromes) from the last 48 hours
+    /// See `aeroweb::client::Client::get_maa`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_maa(&self, options: crate::maa::RequestOptions) -> Result<crate::maa::Maa, Error> {
+        self.runtime.block_on(self.inner.get_maa(options))
+    }
+
+    /// Retrieves a list of aeronautical maps (TEMSI et WINTEM)
+    /// See `aeroweb::client::Client::get_maps`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_maps(
+        &self,
+        options: crate::maps::RequestOptions,
+    ) -> Result<crate::maps::Maps, Error> {
+        self.runtime.block_on(self.inner.get_maps(options))
+    }
+
+    /// Retrieves OPMET data (METAR, SPECI, TAF, SIGMET, ...) for a list of airports
+    /// See `aeroweb::client::Client::get_opmet`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_opmet(
+        &self,
+        options: crate::opmet::RequestOptions,
+    ) -> Result<crate::opmet::Opmet, Error> {
+        self.runtime.block_on(self.inner.get_opmet(options))
+    }
+
+    /// Retrieves PREDECs (`PREvision DECollage`)
+    /// See `aeroweb::client::Client::get_predec`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_predec(
+        &self,
+        options: crate::predec::RequestOptions,
+    ) -> Result<crate::predec::Predec, Error> {
+        self.runtime.block_on(self.inner.get_predec(options))
+    }
+
+    /// Retrieves SIGMETs and/or AIRMETs and/or GAMETs for a list of FIR and/or airports
+    /// See `aeroweb::client::Client::get_sigmet`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_sigmet(
+        &self,
+        options: crate::sigmet::RequestOptions,
+    ) -> Result<crate::sigmet::Sigmet, Error> {
+        self.runtime.block_on(self.inner.get_sigmet(options))
+    }
+
+    /// Retrieves SIGMETs and/or AIRMETs and/or GAMETs for a list of airports and their FIRs
+    /// See `aeroweb::client::Client::get_sigmet_for_airports`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the FIR of an airport is unknown, or if one of the requests fails or
+    /// its XML cannot be parsed.
+    ///
+    pub fn get_sigmet_for_airports(
+        &self,
+        airports: &[Airport],
+        geodata: &Geodata,
+    ) -> Result<crate::sigmet::Sigmet, Error> {
+        self.runtime
+            .block_on(self.inner.get_sigmet_for_airports(airports, geodata))
+    }
+
+    /// Retrieves the OPMET, MAA, PREDEC, SIGMET and space weather products of a set of airports
+    /// See `aeroweb::client::Client::get_snapshot`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no airports nor locations.
+    ///
+    pub fn get_snapshot(
+        &self,
+        options: crate::snapshot::RequestOptions,
+        geodata: &Geodata,
+    ) -> Result<crate::snapshot::Snapshot, Error> {
+        self.runtime
+            .block_on(self.inner.get_snapshot(options, geodata))
+    }
+
+    /// Retrieves Space Weather Advisories
+    /// See `aeroweb::client::Client::get_sw`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_sw(&self) -> Result<crate::sw::SpaceWeather, Error> {
+        self.runtime.block_on(self.inner.get_sw())
+    }
+
+    /// Retrieves tropical cyclone warning messages for a list of producing centers
+    /// See `aeroweb::client::Client::get_tca`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_tca(&self, options: crate::tca::RequestOptions) -> Result<crate::tca::Tca, Error> {
+        self.runtime.block_on(self.inner.get_tca(options))
+    }
+
+    /// Retrieves tropical cyclone warning graphics for a list of producing centers
+    /// See `aeroweb::client::Client::get_tcag`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_tcag(
+        &self,
+        options: crate::tcag::RequestOptions,
+    ) -> Result<crate::tcag::Tcag, Error> {
+        self.runtime.block_on(self.inner.get_tcag(options))
+    }
+
+    /// Retrieves volcanic ash warning messages for a list of producing centers
+    /// See `aeroweb::client::Client::get_vaa`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_vaa(&self, options: crate::vaa::RequestOptions) -> Result<crate::vaa::Vaa, Error> {
+        self.runtime.block_on(self.inner.get_vaa(options))
+    }
+
+    /// Retrieves volcanic ash warning graphics for a list of producing centers
+    /// See `aeroweb::client::Client::get_vag`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the XML cannot be parsed.
+    ///
+    pub fn get_vag(&self, options: crate::vag::RequestOptions) -> Result<crate::vag::Vag, Error> {
+        self.runtime.block_on(self.inner.get_vag(options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport::Airport;
+    use crate::transport::MemoryTransport;
+
+    #[test]
+    fn test_blocking_client() {
+        let client = Client::with_transport(MemoryTransport::from_dir("./data").unwrap());
+
+        let res = client
+            .get_opmet(crate::opmet::RequestOptions {
+                airports: vec![Airport::LFBO, Airport::LFBA],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(res.reports.len(), 2);
+
+        let res = client.get_sw().unwrap();
+        assert_eq!(res.reports.len(), 7);
+
+        assert!(matches!(
+            client.get_maa(crate::maa::RequestOptions::default()),
+            Err(Error::InvalidOptions(_))
+        ));
+
+        assert_eq!(client.usage().total_today(), 2);
+    }
+}
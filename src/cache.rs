@@ -0,0 +1,177 @@
+use crate::error::Error;
+use crate::helpers::file_key;
+use crate::product::Product;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Disk-backed store of raw API responses, consulted by the `Client` before hitting the network.
+///
+/// Each response is stored as two files in `directory`: the raw XML (`<key>.xml`) and the time
+/// it was fetched, in seconds since the UNIX epoch (`<key>.timestamp`). The key is built from the
+/// `TYPE_DONNEES` and the parameters of the request, e.g. `OPMET2_LIEUID-LFBO-LFBA`, the keys of
+/// the requests with many locations being cut and followed by a hash.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    /// Directory where the responses are stored, created on the first write
+    pub directory: PathBuf,
+
+    /// Age under which a cached response is served without querying the API.
+    /// Default is 5 minutes.
+    pub max_age: Duration,
+
+    /// If `true`, the last known response is served (and marked as stale) when the API cannot
+    /// be reached, whatever its age.
+    /// Default is `false`.
+    pub offline: bool,
+}
+
+/// A response read from the disk cache
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Raw XML as returned by the API
+    pub body: String,
+
+    /// When the response was retrieved from the API
+    pub fetched_at: SystemTime,
+}
+
+impl DiskCache {
+    #[must_use]
+    pub fn new(directory: impl Into<PathBuf>) -> DiskCache {
+        DiskCache {
+            directory: directory.into(),
+            max_age: Duration::from_secs(300),
+            offline: false,
+        }
+    }
+
+    /// Reads the last known response for a request, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cached files exist but cannot be read.
+    ///
+    pub fn read(&self, product: Product, params: &str) -> Result<Option<Entry>, Error> {
+        let path = self.path(product, params);
+
+        let timestamp = match std::fs::read_to_string(path.with_extension("timestamp")) {
+            Ok(timestamp) => timestamp,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::Cache(e)),
+        };
+
+        // A timestamp that cannot be parsed is treated as a missing entry, it will be
+        // overwritten by the next successful fetch
+        let Ok(seconds) = timestamp.trim().parse::<u64>() else {
+            return Ok(None);
+        };
+
+        let body = match std::fs::read_to_string(path.with_extension("xml")) {
+            Ok(body) => body,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::Cache(e)),
+        };
+
+        Ok(Some(Entry {
+            body,
+            fetched_at: UNIX_EPOCH + Duration::from_secs(seconds),
+        }))
+    }
+
+    /// Stores a response freshly retrieved from the API.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or the files cannot be written.
+    ///
+    pub fn write(&self, product: Product, params: &str, body: &str) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.directory).map_err(Error::Cache)?;
+
+        let path = self.path(product, params);
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        // The XML is written first so that a fresh timestamp never points to an outdated body
+        std::fs::write(path.with_extension("xml"), body).map_err(Error::Cache)?;
+        std::fs::write(path.with_extension("timestamp"), seconds.to_string())
+            .map_err(Error::Cache)?;
+
+        Ok(())
+    }
+
+    /// Returns the path of an entry, without extension
+    fn path(&self, product: Product, params: &str) -> PathBuf {
+        Path::new(&self.directory).join(file_key(product, params))
+    }
+}
+
+impl Entry {
+    /// Returns `true` if the response was fetched less than `max_age` ago
+    #[must_use]
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.fetched_at
+            .elapsed()
+            .is_ok_and(|elapsed| elapsed < max_age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport::Airport;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_disk_cache() {
+        let directory = std::env::temp_dir().join(format!("aeroweb-cache-{}", std::process::id()));
+        let cache = DiskCache::new(&directory);
+
+        assert!(cache
+            .read(Product::Opmet, "LIEUID=LFBO|LFBA")
+            .unwrap()
+            .is_none());
+
+        let data = std::fs::read_to_string("./data/opmet2.xml").unwrap();
+        cache
+            .write(Product::Opmet, "LIEUID=LFBO|LFBA", &data)
+            .unwrap();
+
+        assert!(directory.join("OPMET2_LIEUID-LFBO-LFBA.xml").exists());
+        assert!(directory.join("OPMET2_LIEUID-LFBO-LFBA.timestamp").exists());
+
+        let entry = cache
+            .read(Product::Opmet, "LIEUID=LFBO|LFBA")
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.body, data);
+        assert!(entry.is_fresh(cache.max_age));
+        assert!(!entry.is_fresh(Duration::ZERO));
+
+        assert!(cache.read(Product::Opmet, "LIEUID=LFBO").unwrap().is_none());
+
+        let file = DiskCache::new(directory.join("OPMET2_LIEUID-LFBO-LFBA.xml"));
+        assert!(matches!(
+            file.write(Product::Opmet, "LIEUID=LFBO", &data),
+            Err(Error::Cache(_))
+        ));
+
+        // The keys of the requests with many airports are kept within the limits of the file
+        // systems
+        let lieuid = |skip| {
+            let airports = Airport::iter().skip(skip).take(50).map(|a| a.to_string());
+            format!("LIEUID={}", airports.collect::<Vec<_>>().join("|"))
+        };
+        let params = lieuid(0);
+        cache.write(Product::Opmet, &params, &data).unwrap();
+        assert_eq!(
+            cache.read(Product::Opmet, &params).unwrap().unwrap().body,
+            data
+        );
+        assert!(cache.read(Product::Opmet, &lieuid(1)).unwrap().is_none());
+        assert!(file_key(Product::Opmet, &params).len() <= 120);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
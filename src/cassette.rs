@@ -0,0 +1,289 @@
+use crate::error::Error;
+use crate::helpers::file_key;
+use crate::product::Product;
+use crate::transport::Transport;
+use futures::future::{BoxFuture, FutureExt};
+use quick_xml::escape::escape;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+/// The responses of the API to a request, recorded in the order they were received and stored
+/// as a JSON file named after the request in the cassette directory,
+/// e.g. `OPMET2_LIEUID-LFBO-LFBA.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    /// `TYPE_DONNEES` of the request
+    /// e.g. OPMET2, SIGMET2
+    pub product: String,
+
+    /// e.g. LIEUID=LFBO|LFBA
+    pub params: String,
+
+    /// Raw bodies returned by the API, the first one first
+    pub responses: Vec<String>,
+}
+
+/// Records every response of the wrapped transport in a cassette. The responses to the same
+/// request are appended to its cassette, a cassette recorded by a previous recorder being
+/// overwritten.
+///
+/// The secrets are scrubbed from the parameters and from the responses, which may contain the
+/// API key, e.g. `ID=...` in the links of the maps.
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    pub inner: T,
+
+    /// Directory where the cassettes are written, created on the first write
+    pub directory: PathBuf,
+
+    /// Values replaced by `REDACTED` in the requests and the responses, as is and XML-escaped,
+    /// e.g. the API key
+    pub secrets: Vec<String>,
+
+    /// Cassettes recorded so far, per file name
+    recorded: Mutex<HashMap<String, Cassette>>,
+}
+
+/// Serves the cassettes of a directory instead of querying the API. The responses of a cassette
+/// are served in turn, the last one being repeated.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    pub directory: PathBuf,
+
+    /// Number of responses served so far, per file name
+    served: Mutex<HashMap<String, usize>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    #[must_use]
+    pub fn new(inner: T, directory: impl Into<PathBuf>) -> RecordingTransport<T> {
+        RecordingTransport {
+            inner,
+            directory: directory.into(),
+            secrets: vec![],
+            recorded: Mutex::default(),
+        }
+    }
+
+    fn scrub(&self, value: &str) -> String {
+        self.secrets
+            .iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(value.to_string(), |value, secret| {
+                value
+                    .replace(escape(secret).as_ref(), "REDACTED")
+                    .replace(secret.as_str(), "REDACTED")
+            })
+    }
+
+    fn record(&self, product: Product, params: &str, response: &str) -> std::io::Result<()> {
+        let params = self.scrub(params);
+        let key = file_key(product, &params);
+
+        let mut recorded = self.recorded.lock().unwrap_or_else(PoisonError::into_inner);
+        let cassette = recorded.entry(key.clone()).or_insert_with(|| Cassette {
+            product: product.to_string(),
+            params,
+            responses: vec![],
+        });
+        cassette.responses.push(self.scrub(response));
+
+        std::fs::create_dir_all(&self.directory)?;
+        std::fs::write(
+            self.directory.join(key).with_extension("json"),
+            serde_json::to_string_pretty(&cassette)?,
+        )
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn fetch<'a>(
+        &'a self,
+        product: Product,
+        params: &'a str,
+    ) -> BoxFuture<'a, Result<String, Error>> {
+        async move {
+            let res = self.inner.fetch(product, params).await?;
+
+            self.record(product, params, &res)
+                .map_err(|e| Error::Transport(e.into()))?;
+
+            Ok(res)
+        }
+        .boxed()
+    }
+}
+
+impl ReplayTransport {
+    #[must_use]
+    pub fn new(directory: impl Into<PathBuf>) -> ReplayTransport {
+        ReplayTransport {
+            directory: directory.into(),
+            served: Mutex::default(),
+        }
+    }
+
+    /// Reads the cassette recorded for a request
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no cassette for this request or it cannot be read.
+    ///
+    pub fn cassette(&self, product: Product, params: &str) -> Result<Cassette, Error> {
+        let path = self
+            .directory
+            .join(file_key(product, params))
+            .with_extension("json");
+
+        let cassette: Cassette = match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                serde_json::from_str(&content).map_err(|e| Error::Transport(e.into()))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::Transport(
+                    format!("No cassette for {product} {params}").into(),
+                ))
+            }
+            Err(e) => return Err(Error::Transport(e.into())),
+        };
+
+        // Different requests may share the same file name
+        if cassette.product != product.to_string()
+            || cassette.params != params
+            || cassette.responses.is_empty()
+        {
+            return Err(Error::Transport(
+                format!("No cassette for {product} {params}").into(),
+            ));
+        }
+
+        Ok(cassette)
+    }
+
+    /// Returns the next response of the cassette recorded for a request
+    fn next_response(&self, product: Product, params: &str) -> Result<String, Error> {
+        let mut cassette = self.cassette(product, params)?;
+
+        let mut served = self.served.lock().unwrap_or_else(PoisonError::into_inner);
+        let served = served.entry(file_key(product, params)).or_default();
+        let index = (*served).min(cassette.responses.len() - 1);
+        *served += 1;
+
+        Ok(cassette.responses.swap_remove(index))
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn fetch<'a>(
+        &'a self,
+        product: Product,
+        params: &'a str,
+    ) -> BoxFuture<'a, Result<String, Error>> {
+        futures::future::ready(self.next_response(product, params)).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves the responses in turn, whatever the request
+    #[derive(Debug)]
+    struct SequenceTransport(Mutex<Vec<&'static str>>);
+
+    impl Transport for SequenceTransport {
+        fn fetch<'a>(
+            &'a self,
+            _product: Product,
+            _params: &'a str,
+        ) -> BoxFuture<'a, Result<String, Error>> {
+            let body = self.0.lock().unwrap().remove(0);
+
+            futures::future::ready(Ok(body.to_string())).boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let directory =
+            std::env::temp_dir().join(format!("aeroweb-cassettes-{}", std::process::id()));
+
+        let inner = SequenceTransport(Mutex::new(vec![
+            "<lien>/affiche.php?ID=api&amp;key&amp;VUE=1</lien>",
+            "<lien>/affiche.php?ID=api&amp;key&amp;VUE=2</lien>",
+            "ID=api&key",
+        ]));
+
+        let mut recorder = RecordingTransport::new(inner, &directory);
+        recorder.secrets.push("api&key".to_string());
+
+        // The responses are returned as received
+        let res = recorder.fetch(Product::Vag, "LIEUID=RJTD").await.unwrap();
+        assert_eq!(res, "<lien>/affiche.php?ID=api&amp;key&amp;VUE=1</lien>");
+        recorder.fetch(Product::Vag, "LIEUID=RJTD").await.unwrap();
+        recorder
+            .fetch(Product::Vag, "LIEUID=api&key")
+            .await
+            .unwrap();
+
+        // But the secrets are scrubbed from the cassettes, as is and XML-escaped
+        for entry in std::fs::read_dir(&directory).unwrap() {
+            let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(!content.contains("ID=api&"));
+        }
+
+        let player = ReplayTransport::new(&directory);
+        let cassette = player.cassette(Product::Vag, "LIEUID=RJTD").unwrap();
+        assert_eq!(cassette.product, "VAG");
+        assert_eq!(cassette.params, "LIEUID=RJTD");
+        assert_eq!(cassette.responses.len(), 2);
+        let cassette = player.cassette(Product::Vag, "LIEUID=REDACTED").unwrap();
+        assert_eq!(cassette.responses, ["ID=REDACTED"]);
+
+        // The responses are replayed in turn, the last one being repeated
+        for vue in [1, 2, 2] {
+            let res = player.fetch(Product::Vag, "LIEUID=RJTD").await.unwrap();
+            assert_eq!(
+                res,
+                format!("<lien>/affiche.php?ID=REDACTED&amp;VUE={vue}</lien>")
+            );
+        }
+
+        assert!(matches!(
+            player.fetch(Product::Vag, "LIEUID=LFPW").await,
+            Err(Error::Transport(_))
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_many_airports() {
+        use crate::airport::Airport;
+        use strum::IntoEnumIterator;
+
+        let directory =
+            std::env::temp_dir().join(format!("aeroweb-cassettes-many-{}", std::process::id()));
+        let lieuid = |skip| {
+            let codes = Airport::iter()
+                .skip(skip)
+                .take(50)
+                .map(|airport| airport.to_string())
+                .collect::<Vec<_>>();
+            format!("LIEUID={}", codes.join("|"))
+        };
+
+        let inner = SequenceTransport(Mutex::new(vec!["<opmet/>"]));
+        let recorder = RecordingTransport::new(inner, &directory);
+        recorder.fetch(Product::Opmet, &lieuid(0)).await.unwrap();
+
+        let player = ReplayTransport::new(&directory);
+        let res = player.fetch(Product::Opmet, &lieuid(0)).await.unwrap();
+        assert_eq!(res, "<opmet/>");
+        assert!(player.fetch(Product::Opmet, &lieuid(1)).await.is_err());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,23 +1,237 @@
+use crate::airport::Airport;
+use crate::cache::DiskCache;
+use crate::cassette::{RecordingTransport, ReplayTransport};
 use crate::error::Error;
-use crate::helpers::parse;
+use crate::geodata::Geodata;
+use crate::helpers::{parse, Stale};
+use crate::icao::Icao;
+use crate::product::Product;
+use crate::quota::{Quota, RateLimit, Usage};
+use crate::transport::{http_fetch, HttpTransport, Transport, API_BASE_URL};
+use futures::channel::oneshot;
+use futures::future::{join_all, try_join_all, FutureExt, Shared};
+use futures::stream::BoxStream;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
 
 #[derive(Debug)]
 pub struct Client {
+    /// HTTP client sending the requests to `api_base_url`, unless a `transport` is set
     pub http_client: reqwest::Client,
     pub api_base_url: &'static str,
     pub api_key: String,
+
+    /// Sends the requests instead of `http_client`, e.g. a `MemoryTransport` serving fixtures.
+    /// Default is `None`.
+    pub transport: Option<Arc<dyn Transport>>,
+
+    /// Disk cache consulted before hitting the network, disabled by default
+    pub cache: Option<DiskCache>,
+
+    /// Limits the number of requests sent to the API, disabled by default
+    pub rate_limit: Option<RateLimit>,
+
+    /// Rate limiting state and number of requests sent per product and per day
+    quota: Quota,
+
+    /// Requests being fetched, shared with the concurrent identical requests
+    in_flight: Mutex<HashMap<String, InFlight>>,
+}
+
+/// Response of a request being fetched, shared with the concurrent identical requests
+type InFlight = Shared<oneshot::Receiver<Result<Fetched, Arc<Error>>>>;
+
+/// Raw response of the API
+#[derive(Debug, Clone)]
+struct Fetched {
+    body: String,
+
+    /// `true` if the body is an outdated response served by the disk cache in offline mode
+    stale: bool,
 }
 
 impl Client {
     #[must_use]
     pub fn new(api_key: &str) -> Client {
-        let http_client = reqwest::Client::new();
-
         Client {
-            http_client,
-            api_base_url: "https://aviation.meteo.fr/FR/aviation/serveur_donnees.jsp",
+            http_client: reqwest::Client::new(),
+            api_base_url: API_BASE_URL,
             api_key: String::from(api_key),
+            transport: None,
+            cache: None,
+            rate_limit: None,
+            quota: Quota::default(),
+            in_flight: Mutex::default(),
+        }
+    }
+
+    /// Creates a client recording every response of the API as a cassette in `directory`, with
+    /// the API key scrubbed from the requests, to be served later by a client in replay mode
+    #[must_use]
+    pub fn recording(api_key: &str, directory: impl Into<PathBuf>) -> Client {
+        let mut transport = RecordingTransport::new(HttpTransport::new(api_key), directory);
+        transport.secrets.push(String::from(api_key));
+
+        Client::with_transport(transport)
+    }
+
+    /// Creates a client serving the cassettes recorded in `directory` instead of querying the API
+    #[must_use]
+    pub fn replaying(directory: impl Into<PathBuf>) -> Client {
+        Client::with_transport(ReplayTransport::new(directory))
+    }
+
+    /// Creates a client sending its requests through a custom transport, e.g. a `MemoryTransport`
+    /// serving fixtures in tests
+    #[must_use]
+    pub fn with_transport(transport: impl Transport + 'static) -> Client {
+        Client {
+            transport: Some(Arc::new(transport)),
+            ..Client::new("")
+        }
+    }
+
+    /// Returns the number of requests sent to the API, per product and per day, including the
+    /// ones which got an HTTP error or an invalid API key response. Requests which could not
+    /// be sent, and responses served by the disk cache or shared with a concurrent identical
+    /// request are not counted.
+    #[must_use]
+    pub fn usage(&self) -> Usage {
+        self.quota.usage()
+    }
+
+    /// Retrieves the weather along a route: OPMET and MAA of the airports within the corridor,
+    /// SIGMET/AIRMET/GAMET of the FIRs along the route, TEMSI and WINTEM maps at the cruise level
+    /// The airports are placed with the positions of `geodata` and the FIRs found with its
+    /// boundaries, see `aeroweb::briefing::RequestOptions::airports`. The products are requested
+    /// concurrently, in as many requests as needed to respect the limit of 50 locations. A failed
+    /// request doesn't fail the briefing, its error is kept in the outcome of each airport or FIR
+    /// it was sent for. The airports of the corridor issuing neither METAR nor TAF are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the route has less than 2 waypoints.
+    ///
+    pub async fn get_briefing(
+        &self,
+        options: crate::briefing::RequestOptions,
+        geodata: &Geodata,
+    ) -> Result<crate::briefing::Briefing, Error> {
+        use crate::snapshot::{Batch, Outcome};
+
+        if options.route.len() < 2 {
+            return Err(Error::InvalidOptions(
+                "RequestOptions.route must have at least 2 waypoints".to_string(),
+            ));
         }
+
+        let airports = options.airports(geodata);
+        let firs = options.firs(&airports, geodata);
+        let zone = options.zone(&firs);
+        let codes = airports
+            .iter()
+            .map(|&(airport, _)| airport)
+            .collect::<Vec<_>>();
+        let maps = |card_type, altitude| crate::maps::RequestOptions {
+            complete_base: false,
+            card_type: Some(card_type),
+            altitude,
+            zone: Some(zone),
+        };
+
+        let (opmet, maa, sigmet, temsi, wintem) = futures::join!(
+            join_all(codes.chunks(50).map(|airports| async move {
+                let options = crate::opmet::RequestOptions {
+                    airports: airports.to_vec(),
+                    ..Default::default()
+                };
+
+                Batch::new(airports, self.get_opmet(options).await)
+            })),
+            join_all(codes.chunks(50).map(|airports| async move {
+                let options = crate::maa::RequestOptions {
+                    airports: airports.to_vec(),
+                    ..Default::default()
+                };
+
+                Batch::new(airports, self.get_maa(options).await)
+            })),
+            join_all(
+                crate::sigmet::RequestOptions::for_firs(&firs)
+                    .into_iter()
+                    .map(|options| async {
+                        let firs = options.firs.clone();
+
+                        Batch::new(&firs, self.get_sigmet(options).await)
+                    })
+            ),
+            self.get_maps(maps(crate::maps::CardTypeOption::AeroTemsi, None)),
+            self.get_maps(maps(
+                crate::maps::CardTypeOption::AeroWintem,
+                Some(options.wintem_level())
+            )),
+        );
+
+        let stale = [
+            opmet
+                .iter()
+                .any(|batch| batch.response.as_ref().is_ok_and(|res| res.stale)),
+            maa.iter()
+                .any(|batch| batch.response.as_ref().is_ok_and(|res| res.stale)),
+            sigmet
+                .iter()
+                .any(|batch| batch.response.as_ref().is_ok_and(|res| res.stale)),
+            temsi.as_ref().is_ok_and(|res| res.stale),
+            wintem.as_ref().is_ok_and(|res| res.stale),
+        ];
+        let maps = |res: Result<crate::maps::Maps, Error>| match res {
+            Ok(res) if res.zones.iter().all(|zone| zone.maps.is_empty()) => Outcome::Empty,
+            res => Outcome::from(
+                res.map(|res| res.zones.into_iter().flat_map(|zone| zone.maps).collect()),
+            ),
+        };
+
+        Ok(crate::briefing::Briefing {
+            airports: airports
+                .into_iter()
+                .filter_map(|(airport, distance)| {
+                    let code = airport.to_string();
+                    let opmet = Batch::find(&opmet, &code);
+                    let on_route = options
+                        .route
+                        .iter()
+                        .any(|waypoint| waypoint.airport == Some(airport));
+                    let reporting = match &opmet {
+                        Outcome::Ok(report) => {
+                            report.metar.is_some() || report.speci.is_some() || report.taf.is_some()
+                        }
+                        Outcome::Failed(_) => true,
+                        Outcome::Unsupported | Outcome::Empty => false,
+                    };
+
+                    (on_route || reporting).then(|| crate::briefing::AirportBriefing {
+                        airport,
+                        distance,
+                        opmet,
+                        maa: Batch::find(&maa, &code),
+                    })
+                })
+                .collect(),
+            firs: firs
+                .into_iter()
+                .map(|fir| crate::briefing::FirBriefing {
+                    fir,
+                    sigmet: Batch::find(&sigmet, &fir.to_string()),
+                })
+                .collect(),
+            temsi: maps(temsi),
+            wintem: maps(wintem),
+            route: options.route,
+            level: options.level,
+            stale: stale.contains(&true),
+        })
     }
 
     /// Retrieves pre-established flight plans
@@ -33,7 +247,7 @@ impl Client {
     ) -> Result<crate::flight_plan::FlightPlan, Error> {
         let params = format!("DESTINATION={}", options.destination.unwrap_or_default());
 
-        parse(&self.fetch("DOSSIER", &params).await?)
+        parse_fetched(&self.fetch(Product::FlightPlan, &params).await?)
     }
 
     /// Retrieves MAAs (Messages d'Avertissement d'Aérodromes) from the last 48 hours. Only French
@@ -48,23 +262,18 @@ impl Client {
         &self,
         options: crate::maa::RequestOptions,
     ) -> Result<crate::maa::Maa, Error> {
-        if options.airports.is_empty() || options.airports.len() > 50 {
+        let count = options.airports.len() + options.locations.len();
+
+        if count == 0 || count > 50 {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports must be between 1 and 50".to_string(),
+                "RequestOptions.airports and RequestOptions.locations must be between 1 and 50 combined"
+                    .to_string(),
             ));
         }
 
-        let params = format!(
-            "LIEUID={}",
-            options
-                .airports
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>()
-                .join("|")
-        );
+        let params = lieuid(&options.airports, &options.locations);
 
-        parse(&self.fetch("MAA", &params).await?)
+        parse_fetched(&self.fetch(Product::Maa, &params).await?)
     }
 
     /// Retrieves a list of aeronautical maps (TEMSI et WINTEM).
@@ -87,7 +296,7 @@ impl Client {
             )
         };
 
-        parse(&self.fetch("CARTES", &params).await?)
+        parse_fetched(&self.fetch(Product::Maps, &params).await?)
     }
 
     /// Retrieves OPMET data (METAR, SPECI, TAF, SIGMET, ...) for a list of airports (50 max for
@@ -101,23 +310,18 @@ impl Client {
         &self,
         options: crate::opmet::RequestOptions,
     ) -> Result<crate::opmet::Opmet, Error> {
-        if options.airports.is_empty() || options.airports.len() > 50 {
+        let count = options.airports.len() + options.locations.len();
+
+        if count == 0 || count > 50 {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports must be between 1 and 50".to_string(),
+                "RequestOptions.airports and RequestOptions.locations must be between 1 and 50 combined"
+                    .to_string(),
             ));
         }
 
-        let params = format!(
-            "LIEUID={}",
-            options
-                .airports
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>()
-                .join("|")
-        );
+        let params = lieuid(&options.airports, &options.locations);
 
-        parse(&self.fetch("OPMET2", &params).await?)
+        parse_fetched(&self.fetch(Product::Opmet, &params).await?)
     }
 
     /// Retrieves PREDECs (`PREvision DECollage`).
@@ -131,23 +335,16 @@ impl Client {
         &self,
         options: crate::predec::RequestOptions,
     ) -> Result<crate::predec::Predec, Error> {
-        if options.airports.is_empty() {
+        if options.airports.is_empty() && options.locations.is_empty() {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports must be between 1 and 50".to_string(),
+                "RequestOptions.airports and RequestOptions.locations must be at least 1 combined"
+                    .to_string(),
             ));
         }
 
-        let params = format!(
-            "LIEUID={}",
-            options
-                .airports
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>()
-                .join("|")
-        );
+        let params = lieuid(&options.airports, &options.locations);
 
-        parse(&self.fetch("PREDEC", &params).await?)
+        parse_fetched(&self.fetch(Product::Predec, &params).await?)
     }
 
     /// Retrieves SIGMETs and/or AIRMETs and/or GAMETs for a list of FIR and/or airports
@@ -162,33 +359,193 @@ impl Client {
         &self,
         options: crate::sigmet::RequestOptions,
     ) -> Result<crate::sigmet::Sigmet, Error> {
-        if (options.airports.is_empty() && options.firs.is_empty())
-            || (options.airports.len() + options.firs.len() > 50)
-        {
+        let count = options.airports.len() + options.firs.len() + options.locations.len();
+
+        if count == 0 || count > 50 {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports and RequestOptions.first must be between 1 and 50 combined".to_string(),
+                "RequestOptions.airports, RequestOptions.firs and RequestOptions.locations must be between 1 and 50 combined".to_string(),
             ));
         }
 
-        let places = {
-            let mut airports = options
+        let params = lieuid(
+            &options
                 .airports
                 .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>();
-            let mut firs = options
-                .firs
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>();
+                .map(|&airport| Icao::from(airport))
+                .chain(options.firs.iter().map(|&fir| Icao::from(fir)))
+                .collect::<Vec<_>>(),
+            &options.locations,
+        );
 
-            airports.append(&mut firs);
-            airports
+        parse_fetched(&self.fetch(Product::Sigmet, &params).await?)
+    }
+
+    /// Retrieves SIGMETs and/or AIRMETs and/or GAMETs for a list of airports and the FIRs
+    /// containing them, in as many concurrent requests as needed to respect the limit of 50
+    /// locations
+    /// See `aeroweb::sigmet::RequestOptions::for_airports`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the FIR of an airport is unknown, or if one of the requests fails or
+    /// its XML cannot be parsed.
+    ///
+    pub async fn get_sigmet_for_airports(
+        &self,
+        airports: &[Airport],
+        geodata: &Geodata,
+    ) -> Result<crate::sigmet::Sigmet, Error> {
+        let responses = try_join_all(
+            crate::sigmet::RequestOptions::for_airports(airports, geodata)?
+                .into_iter()
+                .map(|options| self.get_sigmet(options)),
+        )
+        .await?;
+
+        let mut sigmet = crate::sigmet::Sigmet {
+            reports: vec![],
+            stale: false,
         };
 
-        let params = format!("LIEUID={}", places.join("|"));
+        for res in responses {
+            sigmet.stale |= res.stale;
+            for report in res.reports {
+                if !sigmet
+                    .reports
+                    .iter()
+                    .any(|existing| existing.oaci == report.oaci)
+                {
+                    sigmet.reports.push(report);
+                }
+            }
+        }
 
-        parse(&self.fetch("SIGMET2", &params).await?)
+        Ok(sigmet)
+    }
+
+    /// Retrieves concurrently the OPMET, MAA, PREDEC, SIGMET and space weather products of a set
+    /// of airports, skipping the products an airport doesn't emit. A failed request doesn't fail
+    /// the snapshot, its error is kept in the outcome of each location it was sent for. The
+    /// SIGMETs of an airport are those of its FIR, see `aeroweb::geodata::Geodata::fir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no airports nor locations.
+    ///
+    pub async fn get_snapshot(
+        &self,
+        options: crate::snapshot::RequestOptions,
+        geodata: &Geodata,
+    ) -> Result<crate::snapshot::Snapshot, Error> {
+        use crate::snapshot::{AirportSnapshot, Batch, Outcome, Snapshot};
+
+        let locations = options.all_locations();
+
+        if locations.is_empty() {
+            return Err(Error::InvalidOptions(
+                "RequestOptions.airports and RequestOptions.locations must be at least 1 combined"
+                    .to_string(),
+            ));
+        }
+
+        let airports = locations
+            .iter()
+            .filter_map(|location| location.as_str().parse::<Airport>().ok())
+            .collect::<Vec<_>>();
+        let predec = locations
+            .iter()
+            .filter_map(|location| {
+                location
+                    .as_str()
+                    .parse::<crate::predec::AirportOption>()
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+        let mut firs = Vec::new();
+
+        for fir in airports.iter().filter_map(|&airport| geodata.fir(airport)) {
+            if !firs.contains(&fir) {
+                firs.push(fir);
+            }
+        }
+
+        let (opmet, maa, predec, sigmet, space_weather) = futures::join!(
+            join_all(locations.chunks(50).map(|locations| async move {
+                let options = crate::opmet::RequestOptions {
+                    locations: locations.to_vec(),
+                    ..Default::default()
+                };
+
+                Batch::new(locations, self.get_opmet(options).await)
+            })),
+            join_all(airports.chunks(50).map(|airports| async move {
+                let options = crate::maa::RequestOptions {
+                    airports: airports.to_vec(),
+                    ..Default::default()
+                };
+
+                Batch::new(airports, self.get_maa(options).await)
+            })),
+            join_all(predec.chunks(50).map(|airports| async move {
+                let options = crate::predec::RequestOptions {
+                    airports: airports.to_vec(),
+                    ..Default::default()
+                };
+
+                Batch::new(airports, self.get_predec(options).await)
+            })),
+            join_all(
+                crate::sigmet::RequestOptions::for_firs(&firs)
+                    .into_iter()
+                    .map(|options| async {
+                        let firs = options.firs.clone();
+
+                        Batch::new(&firs, self.get_sigmet(options).await)
+                    })
+            ),
+            self.get_sw(),
+        );
+
+        let stale = [
+            opmet
+                .iter()
+                .any(|batch| batch.response.as_ref().is_ok_and(|res| res.stale)),
+            maa.iter()
+                .any(|batch| batch.response.as_ref().is_ok_and(|res| res.stale)),
+            predec
+                .iter()
+                .any(|batch| batch.response.as_ref().is_ok_and(|res| res.stale)),
+            sigmet
+                .iter()
+                .any(|batch| batch.response.as_ref().is_ok_and(|res| res.stale)),
+            space_weather.as_ref().is_ok_and(|res| res.stale),
+        ];
+
+        Ok(Snapshot {
+            airports: locations
+                .into_iter()
+                .map(|location| {
+                    let code = location.as_str();
+                    let sigmet = match code.parse::<Airport>() {
+                        Ok(airport) => match geodata.require_fir(airport) {
+                            Ok(fir) => Batch::find(&sigmet, &fir.to_string()),
+                            Err(e) => Outcome::Failed(Arc::new(e)),
+                        },
+                        Err(_) => Outcome::Unsupported,
+                    };
+
+                    AirportSnapshot {
+                        opmet: Batch::find(&opmet, code),
+                        maa: Batch::find(&maa, code),
+                        predec: Batch::find(&predec, code),
+                        sigmet,
+                        location,
+                    }
+                })
+                .collect(),
+            space_weather: space_weather.into(),
+            stale: stale.contains(&true),
+        })
     }
 
     /// Retrieves Space Weather Advisories
@@ -199,7 +556,7 @@ impl Client {
     /// Returns an error if the request fails or the XML cannot be parsed.
     ///
     pub async fn get_sw(&self) -> Result<crate::sw::SpaceWeather, Error> {
-        parse(&self.fetch("SW", "").await?)
+        parse_fetched(&self.fetch(Product::SpaceWeather, "").await?)
     }
 
     /// Retrieves tropical cyclone warning messages for a list of producing centers.
@@ -213,23 +570,16 @@ impl Client {
         &self,
         options: crate::tca::RequestOptions,
     ) -> Result<crate::tca::Tca, Error> {
-        if options.airports.is_empty() {
+        if options.airports.is_empty() && options.locations.is_empty() {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports must be at least 1".to_string(),
+                "RequestOptions.airports and RequestOptions.locations must be at least 1 combined"
+                    .to_string(),
             ));
         }
 
-        let params = format!(
-            "LIEUID={}",
-            options
-                .airports
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>()
-                .join("|")
-        );
+        let params = lieuid(&options.airports, &options.locations);
 
-        parse(&self.fetch("TCA", &params).await?)
+        parse_fetched(&self.fetch(Product::Tca, &params).await?)
     }
 
     /// Retrieves tropical cyclone warning graphics for a list of producing centers.
@@ -243,23 +593,16 @@ impl Client {
         &self,
         options: crate::tcag::RequestOptions,
     ) -> Result<crate::tcag::Tcag, Error> {
-        if options.airports.is_empty() {
+        if options.airports.is_empty() && options.locations.is_empty() {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports must be at least 1".to_string(),
+                "RequestOptions.airports and RequestOptions.locations must be at least 1 combined"
+                    .to_string(),
             ));
         }
 
-        let params = format!(
-            "LIEUID={}",
-            options
-                .airports
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>()
-                .join("|")
-        );
+        let params = lieuid(&options.airports, &options.locations);
 
-        parse(&self.fetch("TCAG", &params).await?)
+        parse_fetched(&self.fetch(Product::Tcag, &params).await?)
     }
 
     /// Retrieves volcanic ash warning messages for a list of producing centers.
@@ -273,23 +616,16 @@ impl Client {
         &self,
         options: crate::vaa::RequestOptions,
     ) -> Result<crate::vaa::Vaa, Error> {
-        if options.airports.is_empty() {
+        if options.airports.is_empty() && options.locations.is_empty() {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports must be at least 1".to_string(),
+                "RequestOptions.airports and RequestOptions.locations must be at least 1 combined"
+                    .to_string(),
             ));
         }
 
-        let params = format!(
-            "LIEUID={}",
-            options
-                .airports
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>()
-                .join("|")
-        );
+        let params = lieuid(&options.airports, &options.locations);
 
-        parse(&self.fetch("VAA", &params).await?)
+        parse_fetched(&self.fetch(Product::Vaa, &params).await?)
     }
 
     /// Retrieves volcanic hash warning graphics for a list of producing centers.
@@ -303,39 +639,161 @@ impl Client {
         &self,
         options: crate::vag::RequestOptions,
     ) -> Result<crate::vag::Vag, Error> {
-        if options.airports.is_empty() {
+        if options.airports.is_empty() && options.locations.is_empty() {
             return Err(Error::InvalidOptions(
-                "RequestOptions.airports must be at least 1".to_string(),
+                "RequestOptions.airports and RequestOptions.locations must be at least 1 combined"
+                    .to_string(),
             ));
         }
 
-        let params = format!(
-            "LIEUID={}",
-            options
-                .airports
-                .iter()
-                .map(std::string::ToString::to_string)
-                .collect::<Vec<_>>()
-                .join("|")
-        );
+        let params = lieuid(&options.airports, &options.locations);
 
-        parse(&self.fetch("VAG", &params).await?)
+        parse_fetched(&self.fetch(Product::Vag, &params).await?)
     }
 
-    /// Retrieves the data from the API
+    /// Polls OPMET, MAA and/or SIGMET at their own interval and streams the changes of their
+    /// messages: new, amended, cancelled or expired ones, per location. The stream never ends, a
+    /// failed poll is emitted as an error and the product is polled again at its next interval.
+    /// The SIGMETs of the airports are those of their FIR, see `aeroweb::geodata::Geodata::fir`.
+    /// e.g. `while let Some(change) = client.watch(options, &geodata)?.next().await { ... }`
     ///
-    async fn fetch(&self, data_type: &str, params: &str) -> Result<String, Error> {
-        let res = self
-            .http_client
-            .get(format!(
-                "{}?ID={}&TYPE_DONNEES={data_type}&{params}",
-                self.api_base_url, self.api_key
-            ))
-            .send()
-            .await?
-            .text()
+    /// # Errors
+    ///
+    /// Returns an error if there is no location or product to watch, if a product cannot be
+    /// watched, or if SIGMET is watched and the FIR of an airport is unknown.
+    ///
+    pub fn watch(
+        &self,
+        options: crate::watch::RequestOptions,
+        geodata: &Geodata,
+    ) -> Result<BoxStream<'_, Result<crate::watch::Change, Error>>, Error> {
+        crate::watch::watch(self, options, geodata)
+    }
+
+    /// Retrieves the data, concurrent identical requests sharing a single fetch. If it fails, the
+    /// waiting requests fail with `Error::Shared` instead of retrying on their own.
+    ///
+    async fn fetch(&self, product: Product, params: &str) -> Result<Fetched, Error> {
+        let key = format!("{product}?{params}");
+        let (sender, receiver) = oneshot::channel();
+
+        let shared = match self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key.clone())
+        {
+            Entry::Occupied(entry) => Some(entry.get().clone()),
+            Entry::Vacant(entry) => {
+                entry.insert(receiver.shared());
+                None
+            }
+        };
+
+        if let Some(shared) = shared {
+            return match shared.await {
+                Ok(Ok(fetched)) => Ok(fetched),
+                Ok(Err(e)) => Err(Error::Shared(e)),
+                // The request is retried if the leading one was cancelled, one of the waiting
+                // requests becoming the new leader
+                Err(oneshot::Canceled) => Box::pin(self.fetch(product, params)).await,
+            };
+        }
+
+        let guard = InFlightGuard {
+            in_flight: &self.in_flight,
+            key,
+        };
+        let res = self.fetch_cached(product, params).await;
+        drop(guard);
+
+        match res {
+            Ok(fetched) => {
+                // The waiting requests may have been dropped in the meantime
+                let _ = sender.send(Ok(fetched.clone()));
+
+                Ok(fetched)
+            }
+            Err(e) => {
+                let e = Arc::new(e);
+                let _ = sender.send(Err(Arc::clone(&e)));
+
+                // The error is only shared if a request is waiting for it
+                Err(Arc::try_unwrap(e).unwrap_or_else(Error::Shared))
+            }
+        }
+    }
+
+    /// Retrieves the data from the disk cache if it is fresh enough, or from the API otherwise
+    ///
+    async fn fetch_cached(&self, product: Product, params: &str) -> Result<Fetched, Error> {
+        let Some(cache) = &self.cache else {
+            return Ok(Fetched {
+                body: self.fetch_api(product, params).await?,
+                stale: false,
+            });
+        };
+
+        // The disk cache is best effort, an entry that cannot be read is treated as a missing one
+        let entry = cache.read(product, params).unwrap_or_default();
+
+        if let Some(entry) = &entry {
+            if entry.is_fresh(cache.max_age) {
+                return Ok(Fetched {
+                    body: entry.body.clone(),
+                    stale: false,
+                });
+            }
+        }
+
+        match self.fetch_api(product, params).await {
+            Ok(body) => {
+                // The response was retrieved, failing to cache it must not fail the request
+                let _ = cache.write(product, params, &body);
+
+                Ok(Fetched { body, stale: false })
+            }
+            Err(e @ (Error::Fetch(_) | Error::Transport(_))) => match entry {
+                Some(entry) if cache.offline => Ok(Fetched {
+                    body: entry.body,
+                    stale: true,
+                }),
+                _ => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Retrieves the data from the API through the transport
+    ///
+    async fn fetch_api(&self, product: Product, params: &str) -> Result<String, Error> {
+        let permit = self
+            .quota
+            .acquire(self.rate_limit.as_ref(), product)
             .await?;
 
+        let res = match &self.transport {
+            Some(transport) => transport.fetch(product, params).await,
+            None => {
+                http_fetch(
+                    &self.http_client,
+                    self.api_base_url,
+                    &self.api_key,
+                    product,
+                    params,
+                )
+                .await
+            }
+        };
+
+        // Every request which may have reached the server is charged, even if it failed
+        match &res {
+            Ok(body) => permit.sent(!body.contains("<code>NOK</code>")),
+            Err(Error::Fetch(e)) if !e.is_connect() && !e.is_builder() => permit.sent(false),
+            Err(_) => {}
+        }
+
+        let res = res?;
         if res.contains("<code>NOK</code>") {
             return Err(Error::InvalidApiKey);
         }
@@ -343,3 +801,454 @@ impl Client {
         Ok(res)
     }
 }
+
+/// Removes a request from the in-flight ones once it is done, even if it was cancelled
+struct InFlightGuard<'a> {
+    in_flight: &'a Mutex<HashMap<String, InFlight>>,
+    key: String,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.key);
+    }
+}
+
+/// Parses the XML of a response into a `T` struct, flagging it if it is stale.
+///
+fn parse_fetched<T: for<'de> serde::Deserialize<'de> + Stale>(
+    fetched: &Fetched,
+) -> Result<T, Error> {
+    let mut data: T = parse(&fetched.body)?;
+    data.set_stale(fetched.stale);
+
+    Ok(data)
+}
+
+/// Returns the `LIEUID` parameter of a request, e.g. `LIEUID=LFBO|LFBA|EGLL`
+///
+fn lieuid<T: std::fmt::Display>(codes: &[T], locations: &[Icao]) -> String {
+    let codes = codes
+        .iter()
+        .map(ToString::to_string)
+        .chain(locations.iter().map(ToString::to_string))
+        .collect::<Vec<_>>();
+
+    format!("LIEUID={}", codes.join("|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport::Airport;
+    use crate::transport::MemoryTransport;
+    use futures::future::BoxFuture;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Counts the requests, answered after a delay
+    #[derive(Debug)]
+    struct CountingTransport {
+        inner: MemoryTransport,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl Transport for CountingTransport {
+        fn fetch<'a>(
+            &'a self,
+            product: Product,
+            params: &'a str,
+        ) -> BoxFuture<'a, Result<String, Error>> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                self.inner.fetch(product, params).await
+            }
+            .boxed()
+        }
+    }
+
+    fn opmet_options() -> crate::opmet::RequestOptions {
+        crate::opmet::RequestOptions {
+            airports: vec![Airport::LFBO, Airport::LFBA],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport() {
+        let client = Client::with_transport(MemoryTransport::from_dir("./data").unwrap());
+
+        let res = client.get_opmet(opmet_options()).await.unwrap();
+        assert_eq!(res.reports.len(), 2);
+
+        let res = client.get_sw().await.unwrap();
+        assert_eq!(res.reports.len(), 7);
+
+        let res = client
+            .get_fligh_plan(crate::flight_plan::RequestOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(res.locations.len(), 29);
+
+        let mut transport = MemoryTransport::new();
+        transport.insert(Product::Opmet, "<code>NOK</code>");
+        let client = Client::with_transport(transport);
+        assert!(matches!(
+            client.get_opmet(opmet_options()).await,
+            Err(Error::InvalidApiKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_locations() {
+        let locations = vec!["lfbo".parse::<Icao>().unwrap(), "EGLL".parse().unwrap()];
+        assert_eq!(
+            lieuid(&[Airport::LFBA], &locations),
+            "LIEUID=LFBA|LFBO|EGLL"
+        );
+
+        let client = Client::with_transport(MemoryTransport::from_dir("./data").unwrap());
+        let res = client
+            .get_opmet(crate::opmet::RequestOptions {
+                airports: vec![Airport::LFBA],
+                locations: vec!["lfbo".parse().unwrap()],
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.reports.len(), 2);
+
+        // Locations are counted in the limit of 50
+        let res = client
+            .get_opmet(crate::opmet::RequestOptions {
+                airports: vec![Airport::LFBA],
+                locations: vec!["EGLL".parse().unwrap(); 50],
+            })
+            .await;
+        assert!(matches!(res, Err(Error::InvalidOptions(_))));
+    }
+
+    #[tokio::test]
+    async fn test_briefing() {
+        use crate::briefing::{RequestOptions, Waypoint};
+        use crate::snapshot::Outcome;
+
+        let geodata = Geodata::fixture();
+        let route = [Airport::LFBO, Airport::LFBA]
+            .into_iter()
+            .filter_map(|airport| Waypoint::airport(airport, &geodata))
+            .collect::<Vec<_>>();
+
+        let client = Client::with_transport(MemoryTransport::from_dir("./data").unwrap());
+        let res = client
+            .get_briefing(RequestOptions::new(route.clone(), 100), &geodata)
+            .await
+            .unwrap();
+        // The other airports of the corridor have neither METAR nor TAF in the response
+        let airports = res.airports.iter().map(|airport| airport.airport);
+        assert_eq!(airports.collect::<Vec<_>>(), [Airport::LFBO, Airport::LFBA]);
+        assert!(res
+            .airports
+            .iter()
+            .all(|airport| airport.opmet.ok().is_some()));
+        assert_eq!(res.airports[0].maa.ok().unwrap().oaci, "LFBO");
+        assert_eq!(res.firs.len(), 1);
+        assert!(matches!(res.firs[0].sigmet, Outcome::Empty));
+        assert!(res.wintem.ok().is_some_and(|maps| !maps.is_empty()));
+
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json["firs"][0]["sigmet"], "Empty");
+        assert!(json["airports"][0]["maa"]["Ok"].is_object());
+
+        // A failed request only fails its product, the airports of the corridor being kept
+        let mut transport = MemoryTransport::from_dir("./data").unwrap();
+        transport.insert(Product::Opmet, "<code>NOK</code>");
+        let client = Client::with_transport(transport);
+        let res = client
+            .get_briefing(RequestOptions::new(route, 100), &geodata)
+            .await
+            .unwrap();
+        assert!(res.airports.len() > 2);
+        assert!(res
+            .airports
+            .iter()
+            .all(|airport| matches!(airport.opmet.error(), Some(Error::InvalidApiKey))));
+        let lfbo = res
+            .airports
+            .iter()
+            .find(|airport| airport.airport == Airport::LFBO);
+        assert_eq!(lfbo.unwrap().maa.ok().unwrap().oaci, "LFBO");
+        assert!(res.temsi.ok().is_some());
+
+        let json = serde_json::to_value(&res).unwrap();
+        assert!(json["airports"][0]["opmet"]["Failed"].is_string());
+
+        assert!(matches!(
+            client
+                .get_briefing(RequestOptions::new(vec![], 100), &geodata)
+                .await,
+            Err(Error::InvalidOptions(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        use crate::snapshot::{Outcome, RequestOptions};
+
+        let geodata = Geodata::fixture();
+        let options = || RequestOptions {
+            airports: vec![Airport::LFBO, Airport::LFPG, Airport::LFKJ, Airport::LFHN],
+            locations: vec!["EGLL".parse().unwrap()],
+        };
+
+        let client = Client::with_transport(MemoryTransport::from_dir("./data").unwrap());
+        let res = client.get_snapshot(options(), &geodata).await.unwrap();
+        assert!(res.space_weather.ok().is_some());
+
+        let [lfbo, lfpg, lfkj, lfhn, egll] = &res.airports[..] else {
+            panic!("Expected 5 locations");
+        };
+        assert_eq!(lfbo.opmet.ok().unwrap().oaci, "LFBO");
+        assert_eq!(lfbo.maa.ok().unwrap().oaci, "LFBO");
+        assert!(matches!(lfbo.predec, Outcome::Unsupported));
+        assert!(matches!(lfbo.sigmet, Outcome::Empty));
+        assert!(matches!(lfpg.opmet, Outcome::Empty));
+        assert_eq!(lfpg.predec.ok().unwrap().oaci, "LFPG");
+        // Ajaccio is in the FIR of Marseille
+        assert_eq!(lfkj.sigmet.ok().unwrap().oaci, "LFMM");
+        // The position of LFHN is not loaded
+        assert!(matches!(
+            lfhn.sigmet.error(),
+            Some(Error::InvalidGeodata(_))
+        ));
+        assert_eq!(egll.location.as_str(), "EGLL");
+        assert!(matches!(egll.maa, Outcome::Unsupported));
+        assert!(matches!(egll.sigmet, Outcome::Unsupported));
+
+        let json = serde_json::to_value(&res).unwrap();
+        assert_eq!(json["airports"][0]["location"], "LFBO");
+        assert_eq!(json["airports"][0]["predec"], "Unsupported");
+        assert!(json["airports"][2]["sigmet"]["Ok"].is_object());
+        assert!(json["airports"][3]["sigmet"]["Failed"].is_string());
+        assert!(json.get("stale").is_none());
+
+        // A failed request only fails its product
+        let mut transport = MemoryTransport::from_dir("./data").unwrap();
+        transport.insert(Product::Opmet, "<code>NOK</code>");
+        transport.responses.remove(&Product::SpaceWeather);
+        let client = Client::with_transport(transport);
+        let res = client.get_snapshot(options(), &geodata).await.unwrap();
+        assert!(res
+            .airports
+            .iter()
+            .all(|airport| matches!(airport.opmet.error(), Some(Error::InvalidApiKey))));
+        assert_eq!(res.airports[0].maa.ok().unwrap().oaci, "LFBO");
+        assert!(res.space_weather.error().is_some());
+
+        assert!(matches!(
+            client
+                .get_snapshot(RequestOptions::default(), &geodata)
+                .await,
+            Err(Error::InvalidOptions(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sigmet_for_airports() {
+        use strum::IntoEnumIterator;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = Client::with_transport(CountingTransport {
+            inner: MemoryTransport::from_dir("./data").unwrap(),
+            requests: requests.clone(),
+        });
+
+        // 60 airports in the FIR of Bordeaux need two requests
+        let airports = Airport::iter().take(60).collect::<Vec<_>>();
+        let positions = airports
+            .iter()
+            .map(|airport| format!("{airport},43.629101,1.36382,"))
+            .collect::<Vec<_>>();
+        let mut geodata = Geodata::fixture();
+        geodata
+            .load_airports(&format!(
+                "ident,latitude_deg,longitude_deg,elevation_ft\n{}",
+                positions.join("\n")
+            ))
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let res = client
+            .get_sigmet_for_airports(&airports, &geodata)
+            .await
+            .unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        // The FIRs returned by both requests are only kept once
+        let firs = res.reports.iter().map(|report| report.oaci.as_str());
+        assert_eq!(firs.collect::<Vec<_>>(), ["LFMM", "EBBU", "LFRN", "LFRR"]);
+
+        assert!(matches!(
+            client
+                .get_sigmet_for_airports(&[Airport::LFBO], &Geodata::new())
+                .await,
+            Err(Error::InvalidGeodata(_))
+        ));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_offline_cache() {
+        let directory =
+            std::env::temp_dir().join(format!("aeroweb-offline-{}", std::process::id()));
+        let data = std::fs::read_to_string("./data/opmet2.xml").unwrap();
+
+        let mut cache = DiskCache::new(&directory);
+        cache
+            .write(Product::Opmet, "LIEUID=LFBO|LFBA", &data)
+            .unwrap();
+
+        // Without any response, every request fails
+        let mut client = Client::with_transport(MemoryTransport::new());
+        client.cache = Some(cache.clone());
+
+        let res = client.get_opmet(opmet_options()).await.unwrap();
+        assert_eq!(res.reports.len(), 2);
+        assert!(!res.stale);
+
+        cache.max_age = Duration::ZERO;
+        client.cache = Some(cache.clone());
+        assert!(matches!(
+            client.get_opmet(opmet_options()).await,
+            Err(Error::Transport(_))
+        ));
+
+        cache.offline = true;
+        client.cache = Some(cache);
+        let res = client.get_opmet(opmet_options()).await.unwrap();
+        assert_eq!(res.reports.len(), 2);
+        assert!(res.stale);
+
+        // A cache that cannot be written doesn't fail the request
+        let file = directory.join("file");
+        std::fs::write(&file, "").unwrap();
+        let mut transport = MemoryTransport::new();
+        transport.insert(Product::Opmet, data);
+        let mut client = Client::with_transport(transport);
+        client.cache = Some(DiskCache::new(&file));
+        let res = client.get_opmet(opmet_options()).await.unwrap();
+        assert_eq!(res.reports.len(), 2);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn test_http_client() {
+        use crate::mock_server::MockServer;
+
+        let server = MockServer::start(MemoryTransport::from_dir("./data").unwrap(), "api_key")
+            .await
+            .unwrap();
+
+        let mut client = Client::new("api_key");
+        client.api_base_url = Box::leak(server.url().into_boxed_str());
+        let res = client.get_opmet(opmet_options()).await.unwrap();
+        assert_eq!(res.reports.len(), 2);
+
+        client.api_key = String::from("wrong_key");
+        assert!(matches!(
+            client.get_opmet(opmet_options()).await,
+            Err(Error::InvalidApiKey)
+        ));
+        assert_eq!(server.requests(), 2);
+        assert_eq!(client.usage().today(Product::Opmet), 2);
+        assert_eq!(client.usage().successes_today(Product::Opmet), 1);
+    }
+
+    #[cfg(feature = "mock-server")]
+    #[tokio::test]
+    async fn test_offline_cache_server_error() {
+        use crate::mock_server::{Fault, MockServer};
+
+        let directory =
+            std::env::temp_dir().join(format!("aeroweb-server-error-{}", std::process::id()));
+        let server = MockServer::start(MemoryTransport::from_dir("./data").unwrap(), "api_key")
+            .await
+            .unwrap();
+
+        let mut cache = DiskCache::new(&directory);
+        cache.max_age = Duration::ZERO;
+        let mut client = server.client("api_key");
+        client.cache = Some(cache.clone());
+
+        let res = client.get_opmet(opmet_options()).await.unwrap();
+        assert!(!res.stale);
+
+        server.set_fault(Some(Fault::ServerError));
+        assert!(matches!(
+            client.get_opmet(opmet_options()).await,
+            Err(Error::Fetch(_))
+        ));
+        assert_eq!(client.usage().today(Product::Opmet), 2);
+        assert_eq!(client.usage().successes_today(Product::Opmet), 1);
+
+        cache.offline = true;
+        client.cache = Some(cache);
+        let res = client.get_opmet(opmet_options()).await.unwrap();
+        assert_eq!(res.reports.len(), 2);
+        assert!(res.stale);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_coalescing() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let mut inner = MemoryTransport::from_dir("./data").unwrap();
+        inner.responses.remove(&Product::SpaceWeather);
+        let client = Client::with_transport(CountingTransport {
+            inner,
+            requests: requests.clone(),
+        });
+
+        let res =
+            futures::future::join_all((0..5).map(|_| client.get_opmet(opmet_options()))).await;
+        assert!(res
+            .iter()
+            .all(|res| res.as_ref().unwrap().reports.len() == 2));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Once done, the next request hits the API again
+        client.get_opmet(opmet_options()).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert_eq!(client.usage().today(Product::Opmet), 2);
+
+        // When the leading request is cancelled, a single waiting request sends it again
+        let leader =
+            tokio::time::timeout(Duration::from_millis(10), client.get_opmet(opmet_options()));
+        let waiters = futures::future::join_all((0..5).map(|_| client.get_opmet(opmet_options())));
+        let (leader, res) = futures::join!(leader, waiters);
+        assert!(leader.is_err());
+        assert!(res.iter().all(Result::is_ok));
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+
+        // A failure is shared with the waiting requests instead of being retried by each of them
+        let res = futures::future::join_all((0..5).map(|_| client.get_sw())).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 5);
+        assert!(res
+            .iter()
+            .all(|res| matches!(res.as_ref().unwrap_err().root(), Error::Transport(_))));
+        assert!(
+            res.iter()
+                .filter(|res| matches!(res, Err(Error::Shared(_))))
+                .count()
+                >= 4
+        );
+    }
+}
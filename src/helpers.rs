@@ -1,7 +1,9 @@
 use crate::error::Error;
+use quick_xml::events::{BytesCData, BytesDecl, Event};
+use quick_xml::writer::ElementWriter;
 use serde::{de, Deserialize, Deserializer};
 
-/// Appends the host to a link if it's not empty or "NIL".
+/// Appends the host to a link if it's relative, and not empty or "NIL".
 ///
 /// # Errors
 ///
@@ -11,10 +13,13 @@ pub fn de_option_link<'de, D>(deserializer: D) -> Result<Option<String>, D::Erro
 where
     D: Deserializer<'de>,
 {
-    let s = String::deserialize(deserializer)?;
-
-    if s.is_empty() || s == "NIL" {
+    let Some(s) = de_option_string(deserializer)? else {
         return Ok(None);
+    };
+
+    // Links serialized by this library are already absolute
+    if !s.starts_with('/') {
+        return Ok(Some(s));
     }
 
     Ok(Some(
@@ -34,7 +39,7 @@ pub fn de_option_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Er
 where
     D: Deserializer<'de>,
 {
-    let s = String::deserialize(deserializer)?;
+    let s = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
 
     if s.is_empty() || s == "NIL" || s == "NODATA" {
         return Ok(None);
@@ -43,6 +48,178 @@ where
     Ok(Some(s))
 }
 
+/// Implements `Serialize` and `Deserialize` with `Display` and `FromStr`, so that the codes are
+/// read case-insensitively, e.g. "lfbo" for `Airport::LFBO`
+macro_rules! serde_from_str {
+    ($type:ty) => {
+        impl serde::Serialize for $type {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <String as serde::Deserialize>::deserialize(deserializer)?
+                    .parse()
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+pub(crate) use serde_from_str;
+
+/// Implements `Ord` and `PartialOrd` with the codes rather than the declaration order, so that
+/// the values are sorted alphabetically, e.g. `Airport::LFBA` before `Airport::LFBO`
+macro_rules! ord_by_code {
+    ($type:ty) => {
+        impl Ord for $type {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                <&'static str>::from(self).cmp(other.into())
+            }
+        }
+
+        impl PartialOrd for $type {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+    };
+}
+
+pub(crate) use ord_by_code;
+
+/// Implemented by the responses that can be served from the disk cache
+#[cfg_attr(not(feature = "client"), allow(dead_code))]
+pub(crate) trait Stale {
+    fn set_stale(&mut self, stale: bool);
+}
+
+/// Longest file key, leaving room for the extension within the 255 bytes allowed by most file
+/// systems
+#[cfg(feature = "client")]
+const MAX_FILE_KEY: usize = 120;
+
+/// Returns a file name, without extension, identifying a request.
+/// e.g. `OPMET2_LIEUID-LFBO-LFBA`
+///
+/// The keys of the requests with many locations are cut and followed by a hash of the whole
+/// request, e.g. `OPMET2_LIEUID-LFBO-LFBA-..._0123456789abcdef`.
+#[cfg(feature = "client")]
+pub fn file_key(product: crate::product::Product, params: &str) -> String {
+    let request = format!("{product}_{params}");
+    let key = request
+        .chars()
+        .map(|c| match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '-' => c,
+            '=' | '|' => '-',
+            _ => '_',
+        })
+        .collect::<String>();
+
+    if key.len() <= MAX_FILE_KEY {
+        return key;
+    }
+
+    // FNV-1a, which unlike the hasher of the standard library is stable across releases
+    let hash = request
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+
+    format!("{}_{hash:016x}", &key[..MAX_FILE_KEY - 17])
+}
+
+/// Formats a number of days since the UNIX epoch as a `YYYYMMDD` date
+/// Algorithm: <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+#[cfg(any(feature = "client", feature = "storage"))]
+pub(crate) fn format_day(day: u64) -> String {
+    let z = day + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + u64::from(m <= 2);
+
+    format!("{y:04}{m:02}{d:02}")
+}
+
+/// Writer used to serialize the models in the XML format of Meteo France's server
+pub(crate) type XmlWriter = quick_xml::Writer<Vec<u8>>;
+
+/// Writes an XML document with the indentation and declaration of Meteo France's server.
+///
+/// # Errors
+///
+/// Returns an error if the document cannot be written.
+///
+pub(crate) fn write_document(
+    content: impl FnOnce(&mut XmlWriter) -> std::io::Result<()>,
+) -> Result<String, Error> {
+    let mut writer = XmlWriter::new_with_indent(Vec::new(), b' ', 4);
+
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    content(&mut writer)?;
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e).into())
+}
+
+/// Writes an element containing a text in a CDATA section, or `missing` (e.g. NIL, NODATA) if
+/// there is no text, the way the server does.
+///
+/// # Errors
+///
+/// Returns an error if the element cannot be written.
+///
+pub(crate) fn write_cdata(
+    writer: &mut XmlWriter,
+    name: &str,
+    text: Option<&str>,
+    missing: &str,
+) -> std::io::Result<()> {
+    write_cdata_element(writer.create_element(name), text, missing)
+}
+
+/// Same as `write_cdata`, for an element that has been given attributes,
+/// e.g. `writer.create_element("SPACEWEATHER").with_attribute(("ID", "KWNP"))`
+///
+/// # Errors
+///
+/// Returns an error if the element cannot be written.
+///
+pub(crate) fn write_cdata_element(
+    element: ElementWriter<'_, Vec<u8>>,
+    text: Option<&str>,
+    missing: &str,
+) -> std::io::Result<()> {
+    element.write_inner_content(|writer| {
+        // A CDATA section cannot contain "]]>", it's split over several sections
+        for cdata in BytesCData::escaped(text.unwrap_or(missing)) {
+            writer.write_event(Event::CData(cdata))?;
+        }
+
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Returns a link as sent by the server, without the host appended when deserializing it, or
+/// NIL if there is none.
+#[must_use]
+pub(crate) fn link_path(link: Option<&str>) -> &str {
+    link.map_or("NIL", |link| {
+        link.strip_prefix("https://aviation.meteo.fr")
+            .unwrap_or(link)
+    })
+}
+
 /// Parses the XML string into a `T` struct.
 ///
 /// # Errors
@@ -1,7 +1,25 @@
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "client")]
+pub mod cache;
+#[cfg(feature = "client")]
+pub mod cassette;
+#[cfg(feature = "client")]
 pub mod client;
 mod helpers;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
 pub mod models;
+#[cfg(feature = "client")]
+pub mod quota;
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "client")]
+pub mod transport;
 pub mod types;
+#[cfg(feature = "client")]
+pub mod watch;
 
+pub use helpers::parse;
 pub use models::*;
 pub use types::*;
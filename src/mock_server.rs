@@ -0,0 +1,502 @@
+use crate::client::Client;
+use crate::error::Error;
+use crate::product::Product;
+use crate::transport::{HttpTransport, MemoryTransport};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+const PATH: &str = "/FR/aviation/serveur_donnees.jsp";
+
+/// Failure injected in the responses of a `MockServer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Answers with an HTTP 500 error
+    ServerError,
+    /// Closes the connection without answering
+    Disconnect,
+    /// Answers with a truncated XML document
+    InvalidXml,
+}
+
+/// Local stand-in for Meteo France's `serveur_donnees.jsp`, serving fixtures filtered by the
+/// parameters of the requests. The server stops when dropped.
+///
+/// `LIEUID` keeps the locations of the fixture whose `oaci` attribute is requested, `ZONE`,
+/// `VUE_CARTE` and `ALTITUDE` keep the matching maps unless `BASE_COMPLETE=oui`. The `DOSSIER`
+/// fixture is served whatever the `DESTINATION`, and an unknown `ID` is answered with
+/// `<code>NOK</code>`.
+#[derive(Debug)]
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<State>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+#[derive(Debug)]
+struct State {
+    fixtures: HashMap<Product, String>,
+    api_key: String,
+    settings: Mutex<Settings>,
+    requests: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct Settings {
+    delay: Duration,
+    fault: Option<Fault>,
+}
+
+impl MockServer {
+    /// Starts a server on a random local port, accepting `api_key` as the only valid `ID`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server cannot listen on a local port.
+    ///
+    pub async fn start(fixtures: MemoryTransport, api_key: &str) -> Result<MockServer, Error> {
+        MockServer::bind("127.0.0.1:0", fixtures, api_key).await
+    }
+
+    /// Starts a server on the given address, accepting `api_key` as the only valid `ID`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server cannot listen on the address.
+    ///
+    pub async fn bind(
+        address: impl ToSocketAddrs,
+        fixtures: MemoryTransport,
+        api_key: &str,
+    ) -> Result<MockServer, Error> {
+        let listener = TcpListener::bind(address).await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(State {
+            fixtures: fixtures.responses,
+            api_key: String::from(api_key),
+            settings: Mutex::default(),
+            requests: AtomicUsize::new(0),
+        });
+
+        let task = tokio::spawn({
+            let state = state.clone();
+
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(handle(stream, state.clone()));
+                }
+            }
+        });
+
+        Ok(MockServer {
+            address,
+            state,
+            task,
+        })
+    }
+
+    #[must_use]
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns the URL to use as `HttpTransport::api_base_url`
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}{PATH}", self.address)
+    }
+
+    /// Creates a client querying this server
+    #[must_use]
+    pub fn client(&self, api_key: &str) -> Client {
+        let mut transport = HttpTransport::new(api_key);
+        transport.api_base_url = self.url();
+
+        Client::with_transport(transport)
+    }
+
+    /// Delays every response
+    pub fn set_delay(&self, delay: Duration) {
+        self.settings().delay = delay;
+    }
+
+    /// Injects a failure in every response, until reset with `None`
+    pub fn set_fault(&self, fault: Option<Fault>) {
+        self.settings().fault = fault;
+    }
+
+    /// Returns the number of requests received
+    #[must_use]
+    pub fn requests(&self) -> usize {
+        self.state.requests.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the server stops, i.e. forever unless it fails
+    pub async fn wait(mut self) {
+        let _ = (&mut self.task).await;
+    }
+
+    fn settings(&self) -> std::sync::MutexGuard<'_, Settings> {
+        self.state
+            .settings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn handle(mut stream: TcpStream, state: Arc<State>) {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 16 * 1024 {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buffer[..n]),
+        }
+    }
+
+    state.requests.fetch_add(1, Ordering::SeqCst);
+
+    let (delay, fault) = {
+        let settings = state
+            .settings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        (settings.delay, settings.fault)
+    };
+
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+
+    let (status, body) = match fault {
+        Some(Fault::Disconnect) => return,
+        Some(Fault::ServerError) => ("500 Internal Server Error", String::from("Server error")),
+        Some(Fault::InvalidXml) => ("200 OK", String::from("<?xml version=\"1.0\"?>\n<root><op")),
+        None => respond(&String::from_utf8_lossy(&request), &state),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: text/xml; charset=utf-8\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Builds the status and body answering a raw HTTP request
+fn respond(request: &str, state: &State) -> (&'static str, String) {
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("GET "))
+        .and_then(|line| line.split(' ').next())
+        .unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    if path != PATH {
+        return ("404 Not Found", String::from("Not found"));
+    }
+
+    let params = query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (decode(key), decode(value)))
+        .collect::<HashMap<_, _>>();
+    let param = |key: &str| params.get(key).map(String::as_str);
+
+    if param("ID") != Some(state.api_key.as_str()) {
+        return (
+            "200 OK",
+            String::from(
+                "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<validation><code>NOK</code></validation>",
+            ),
+        );
+    }
+
+    let Some((product, fixture)) = state
+        .fixtures
+        .iter()
+        .find(|(product, _)| Some(product.to_string().as_str()) == param("TYPE_DONNEES"))
+    else {
+        return ("400 Bad Request", String::from("Unknown TYPE_DONNEES"));
+    };
+
+    let res = match product {
+        Product::FlightPlan | Product::SpaceWeather => Ok(fixture.clone()),
+        Product::Maps if param("BASE_COMPLETE") == Some("oui") => Ok(fixture.clone()),
+        Product::Maps => filter_maps(
+            fixture,
+            param("ZONE").unwrap_or("AERO_FRANCE"),
+            param("VUE_CARTE").unwrap_or("AERO_WINTEM"),
+            param("ALTITUDE").unwrap_or("100"),
+        ),
+        _ => {
+            let locations = param("LIEUID")
+                .unwrap_or_default()
+                .split('|')
+                .collect::<Vec<_>>();
+
+            retain_elements(fixture, 1, |element, _| {
+                attribute(element, "oaci").is_some_and(|oaci| locations.contains(&oaci.as_str()))
+            })
+        }
+    };
+
+    match res {
+        Ok(body) => ("200 OK", body),
+        Err(_) => ("500 Internal Server Error", String::from("Invalid fixture")),
+    }
+}
+
+/// Keeps the maps of a zone, for a type of map and, for WINTEM maps, an altitude
+fn filter_maps(
+    fixture: &str,
+    zone: &str,
+    card_type: &str,
+    altitude: &str,
+) -> Result<String, quick_xml::Error> {
+    let zone = zone.trim_start_matches("AERO_");
+    let card_type = card_type.trim_start_matches("AERO_");
+    let altitude = altitude.parse::<u32>().unwrap_or_default();
+
+    let zones = retain_elements(fixture, 1, |element, _| {
+        attribute(element, "idz").is_some_and(|idz| idz.eq_ignore_ascii_case(zone))
+    })?;
+
+    retain_elements(&zones, 2, |_, source| {
+        let is_type = source.contains(&format!(">{card_type}</"));
+
+        // e.g. <niveau>FL20-100</niveau>, <niveau>FL50</niveau>
+        let in_range = card_type != "WINTEM"
+            || source
+                .split_once("<niveau>FL")
+                .and_then(|(_, level)| level.split_once('<'))
+                .is_some_and(|(level, _)| {
+                    let (low, high) = level.split_once('-').unwrap_or((level, level));
+                    let low = low.parse::<u32>().unwrap_or(u32::MAX);
+                    let high = high.parse::<u32>().unwrap_or_default();
+
+                    (low..=high).contains(&altitude)
+                });
+
+        is_type && in_range
+    })
+}
+
+/// Removes the elements found at `depth` (the root element being at depth 0) for which `keep`
+/// returns `false`. `keep` receives the opening tag of the element and its source.
+fn retain_elements(
+    xml: &str,
+    depth: usize,
+    mut keep: impl FnMut(&BytesStart, &str) -> bool,
+) -> Result<String, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut res = String::with_capacity(xml.len());
+    let mut current_depth = 0;
+    let mut copied = 0;
+    let mut element: Option<(usize, BytesStart)> = None;
+
+    loop {
+        let start = position(&reader);
+
+        match reader.read_event()? {
+            Event::Eof => break,
+            Event::Start(e) => {
+                if current_depth == depth {
+                    element = Some((start, e.into_owned()));
+                }
+                current_depth += 1;
+            }
+            Event::Empty(e) if current_depth == depth => {
+                let end = position(&reader);
+
+                if !keep(&e, &xml[start..end]) {
+                    res.push_str(&xml[copied..start]);
+                    copied = end;
+                }
+            }
+            Event::End(_) => {
+                current_depth = current_depth.saturating_sub(1);
+
+                if current_depth == depth {
+                    if let Some((start, e)) = element.take() {
+                        let end = position(&reader);
+
+                        if !keep(&e, &xml[start..end]) {
+                            res.push_str(&xml[copied..start]);
+                            copied = end;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    res.push_str(&xml[copied..]);
+
+    Ok(res)
+}
+
+fn position(reader: &Reader<&[u8]>) -> usize {
+    usize::try_from(reader.buffer_position()).unwrap_or_default()
+}
+
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .map(std::borrow::Cow::into_owned)
+}
+
+/// Decodes a percent-encoded query string component
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => res.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        res.push(byte);
+                        i += 2;
+                    }
+                    None => res.push(b'%'),
+                }
+            }
+            byte => res.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&res).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::airport::Airport;
+
+    fn fixtures() -> MemoryTransport {
+        MemoryTransport::from_dir("./data").unwrap()
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("LFBO%7CLFBA"), "LFBO|LFBA");
+        assert_eq!(decode("GRAND%20SUD+OUEST"), "GRAND SUD OUEST");
+        assert_eq!(decode("100%"), "100%");
+    }
+
+    #[tokio::test]
+    async fn test_mock_server() {
+        let server = MockServer::start(fixtures(), "api_key").await.unwrap();
+        let client = server.client("api_key");
+
+        let res = client
+            .get_opmet(crate::opmet::RequestOptions {
+                airports: vec![Airport::LFBA],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.reports.len(), 1);
+        assert_eq!(res.reports[0].oaci, "LFBA");
+
+        let res = client
+            .get_maps(crate::maps::RequestOptions {
+                complete_base: false,
+                card_type: None,
+                altitude: Some(crate::maps::LevelOption::FL180),
+                zone: Some(crate::maps::ZoneOption::Euroc),
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.zones.len(), 1);
+        assert_eq!(res.zones[0].id, "EUROC");
+        assert!(res.zones[0]
+            .maps
+            .iter()
+            .all(|map| map.level == "FL180-300" || map.level == "FL20-450"));
+
+        let res = client
+            .get_maps(crate::maps::RequestOptions {
+                complete_base: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.zones.len(), 2);
+
+        assert_eq!(server.requests(), 3);
+
+        let res = server
+            .client("wrong_key")
+            .get_opmet(crate::opmet::RequestOptions {
+                airports: vec![Airport::LFBO],
+                ..Default::default()
+            })
+            .await;
+        assert!(matches!(res, Err(Error::InvalidApiKey)));
+    }
+
+    #[tokio::test]
+    async fn test_mock_server_faults() {
+        let server = MockServer::start(fixtures(), "api_key").await.unwrap();
+        let client = server.client("api_key");
+        let options = || crate::sigmet::RequestOptions {
+            airports: vec![],
+            firs: vec![crate::fir::Fir::LFMM],
+            ..Default::default()
+        };
+
+        server.set_fault(Some(Fault::Disconnect));
+        assert!(matches!(
+            client.get_sigmet(options()).await,
+            Err(Error::Fetch(_))
+        ));
+
+        server.set_fault(Some(Fault::ServerError));
+        match client.get_sigmet(options()).await {
+            Err(Error::Fetch(e)) => {
+                assert_eq!(e.status(), Some(reqwest::StatusCode::INTERNAL_SERVER_ERROR));
+            }
+            res => panic!("Expected a server error, got {res:?}"),
+        }
+
+        server.set_fault(Some(Fault::InvalidXml));
+        assert!(matches!(
+            client.get_sigmet(options()).await,
+            Err(Error::Deserialize(_))
+        ));
+
+        server.set_fault(None);
+        server.set_delay(Duration::from_millis(50));
+        let start = std::time::Instant::now();
+        let res = client.get_sigmet(options()).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(res.reports.len(), 1);
+        assert_eq!(res.reports[0].oaci, "LFMM");
+    }
+}
@@ -0,0 +1,295 @@
+use crate::airport::Airport;
+use crate::coordinates::{sample_route, Coordinates};
+use crate::fir::Fir;
+use crate::geodata::Geodata;
+use crate::location::Location;
+use crate::map::Map;
+use crate::maps::{LevelOption, ZoneOption};
+use crate::snapshot::Outcome;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+/// Default half-width of the corridor in nautical miles
+const DEFAULT_CORRIDOR: f64 = 25.0;
+
+/// Distance in nautical miles between the positions sampled along the route
+const ROUTE_STEP: f64 = 5.0;
+
+/// Highest flight level covered by the TEMSI FRANCE maps
+const TEMSI_FRANCE_CEILING: u16 = 150;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    /// e.g. LFBO, TOU, 4330N00120E
+    pub name: String,
+
+    pub position: Coordinates,
+
+    /// Airport of the waypoint, always part of the briefing whatever the corridor
+    pub airport: Option<Airport>,
+}
+
+impl Waypoint {
+    #[must_use]
+    pub fn new(name: &str, position: Coordinates) -> Waypoint {
+        Waypoint {
+            name: name.to_string(),
+            position,
+            airport: None,
+        }
+    }
+
+    /// Returns the waypoint of an airport, `None` if its position was not loaded
+    #[must_use]
+    pub fn airport(airport: Airport, geodata: &Geodata) -> Option<Waypoint> {
+        Some(Waypoint {
+            name: airport.to_string(),
+            position: geodata.position(airport)?.coordinates,
+            airport: Some(airport),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestOptions {
+    /// Waypoints of the route, from the departure to the destination
+    /// Minimum 2 waypoints
+    pub route: Vec<Waypoint>,
+
+    /// Cruise flight level, e.g. 100 for FL100
+    pub level: u16,
+
+    /// Half-width of the corridor in nautical miles, airports farther from the route are left out
+    /// Default is 25 NM.
+    pub corridor: f64,
+}
+
+impl RequestOptions {
+    #[must_use]
+    pub fn new(route: Vec<Waypoint>, level: u16) -> RequestOptions {
+        RequestOptions {
+            route,
+            level,
+            corridor: DEFAULT_CORRIDOR,
+        }
+    }
+
+    /// Returns the airports of the route and those within the corridor, along with their
+    /// distance in nautical miles from the departure, ordered along the route. Airports whose
+    /// position was not loaded are left out.
+    #[must_use]
+    pub fn airports(&self, geodata: &Geodata) -> Vec<(Airport, f64)> {
+        let samples = self.samples();
+
+        let mut airports = Airport::iter()
+            .filter_map(|airport| {
+                let position = geodata.position(airport)?.coordinates;
+                let (distance, along) = samples
+                    .iter()
+                    .map(|&(sample, along)| (sample.distance(position), along))
+                    .min_by(|a, b| a.0.total_cmp(&b.0))?;
+
+                let on_route = self
+                    .route
+                    .iter()
+                    .any(|waypoint| waypoint.airport == Some(airport));
+
+                (on_route || distance <= self.corridor).then_some((airport, along))
+            })
+            .collect::<Vec<_>>();
+
+        airports.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        airports
+    }
+
+    /// Returns the FIRs crossed by the route and those of the airports of the briefing, as
+    /// returned by `airports`, in the order they are met along the route
+    #[must_use]
+    pub fn firs(&self, airports: &[(Airport, f64)], geodata: &Geodata) -> Vec<Fir> {
+        let positions = self
+            .route
+            .iter()
+            .map(|waypoint| waypoint.position)
+            .collect::<Vec<_>>();
+
+        let mut firs = geodata.crossed(&positions);
+
+        for fir in airports
+            .iter()
+            .filter_map(|&(airport, _)| geodata.fir(airport))
+        {
+            if !firs.contains(&fir) {
+                firs.push(fir);
+            }
+        }
+
+        firs
+    }
+
+    /// Returns the WINTEM level closest to the cruise level
+    #[must_use]
+    pub fn wintem_level(&self) -> LevelOption {
+        match self.level {
+            ..=35 => LevelOption::FL020,
+            36..=65 => LevelOption::FL050,
+            66..=90 => LevelOption::FL080,
+            91..=120 => LevelOption::FL100,
+            121..=160 => LevelOption::FL140,
+            161..=195 => LevelOption::FL180,
+            196..=225 => LevelOption::FL210,
+            226..=255 => LevelOption::FL240,
+            256..=285 => LevelOption::FL270,
+            286..=310 => LevelOption::FL300,
+            311..=330 => LevelOption::FL320,
+            331..=350 => LevelOption::FL340,
+            351..=375 => LevelOption::FL360,
+            376..=400 => LevelOption::FL390,
+            401..=430 => LevelOption::LF410,
+            431..=465 => LevelOption::FL450,
+            466..=505 => LevelOption::FL480,
+            _ => LevelOption::FL530,
+        }
+    }
+
+    /// Returns `ZoneOption::France` for the low-level routes within the French metropolitan
+    /// FIRs, `ZoneOption::Euroc` otherwise, the FIRs of the route being those returned by `firs`.
+    /// The FIRs are unknown without boundaries, falling back to `ZoneOption::Euroc`.
+    #[must_use]
+    pub fn zone(&self, firs: &[Fir]) -> ZoneOption {
+        let metropolitan = [Fir::LFBB, Fir::LFEE, Fir::LFFF, Fir::LFMM, Fir::LFRR];
+
+        if self.level <= TEMSI_FRANCE_CEILING
+            && !firs.is_empty()
+            && firs.iter().all(|fir| metropolitan.contains(fir))
+        {
+            ZoneOption::France
+        } else {
+            ZoneOption::Euroc
+        }
+    }
+
+    /// Returns the positions sampled every 5 NM along the route, with their distance from the
+    /// departure
+    fn samples(&self) -> Vec<(Coordinates, f64)> {
+        let positions = self
+            .route
+            .iter()
+            .map(|waypoint| waypoint.position)
+            .collect::<Vec<_>>();
+
+        sample_route(&positions, ROUTE_STEP)
+    }
+}
+
+/// Weather along a route, assembled from the OPMET, SIGMET, MAA and maps products
+#[derive(Debug, Clone, Serialize)]
+pub struct Briefing {
+    pub route: Vec<Waypoint>,
+
+    /// Cruise flight level, e.g. 100 for FL100
+    pub level: u16,
+
+    /// Airports of the route, and those within the corridor issuing METARs or TAFs, ordered
+    /// along the route
+    pub airports: Vec<AirportBriefing>,
+
+    /// FIRs along the route, in the order they are met
+    pub firs: Vec<FirBriefing>,
+
+    /// TEMSI maps of the zone of the route
+    pub temsi: Outcome<Vec<Map>>,
+
+    /// WINTEM maps of the zone of the route, at the level closest to the cruise level
+    pub wintem: Outcome<Vec<Map>>,
+
+    /// `true` if one of the responses was served from the disk cache because the API could not
+    /// be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AirportBriefing {
+    pub airport: Airport,
+
+    /// Distance along the route in nautical miles, from the departure
+    pub distance: f64,
+
+    /// METAR, TAF, ... of the airport
+    pub opmet: Outcome<crate::opmet::Data>,
+
+    /// Aerodrome warnings in force
+    pub maa: Outcome<Location>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FirBriefing {
+    pub fir: Fir,
+
+    /// SIGMET, AIRMET and GAMET of the FIR
+    pub sigmet: Outcome<crate::sigmet::Data>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_briefing() {
+        let geodata = Geodata::fixture();
+
+        let route = vec![
+            Waypoint::airport(Airport::LFBO, &geodata).unwrap(),
+            Waypoint::new("CNA", Coordinates::new(45.0, 1.5)),
+            Waypoint::airport(Airport::LFPG, &geodata).unwrap(),
+        ];
+        assert!(Waypoint::airport(Airport::LFHN, &geodata).is_none());
+        let options = RequestOptions::new(route, 100);
+
+        let airports = options.airports(&geodata);
+        assert_eq!(airports[0].1, 0.0);
+        assert!(airports.contains(&(Airport::LFBO, 0.0)));
+        // Airports beyond the destination are as far along the route as the destination
+        let (_, total) = airports.last().unwrap();
+        assert!(airports.contains(&(Airport::LFPG, *total)));
+        assert!(airports.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(airports
+            .iter()
+            .all(|(airport, _)| *airport != Airport::LFMN));
+        assert!(airports
+            .iter()
+            .any(|(airport, _)| *airport == Airport::LFBF));
+
+        let firs = options.firs(&airports, &geodata);
+        assert_eq!(firs[..2], [Fir::LFBB, Fir::LFFF]);
+        assert_eq!(options.wintem_level().to_string(), "100");
+        assert_eq!(options.zone(&firs).to_string(), "AERO_FRANCE");
+        assert_eq!(
+            options.zone(&[Fir::LFFF, Fir::EBBU]).to_string(),
+            "AERO_EUROC"
+        );
+
+        // Without boundaries, the FIRs of the route are unknown
+        let positions = Geodata::new();
+        let firs = options.firs(&options.airports(&positions), &positions);
+        assert!(firs.is_empty());
+        assert_eq!(options.zone(&firs).to_string(), "AERO_EUROC");
+        let firs = options.firs(&airports, &geodata);
+
+        let options = RequestOptions {
+            corridor: 0.0,
+            ..RequestOptions::new(options.route, 350)
+        };
+        assert_eq!(
+            options
+                .airports(&geodata)
+                .iter()
+                .map(|(airport, _)| *airport)
+                .collect::<Vec<_>>(),
+            [Airport::LFBO, Airport::LFPG]
+        );
+        assert_eq!(options.wintem_level().to_string(), "340");
+        assert_eq!(options.zone(&firs).to_string(), "AERO_EUROC");
+    }
+}
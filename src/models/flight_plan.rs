@@ -1,7 +1,14 @@
 use crate::center::Center;
-use crate::helpers::{de_option_link, de_option_string};
+use crate::error::Error;
+use crate::helpers::{
+    de_option_link, de_option_string, link_path, write_cdata, write_document, Stale, XmlWriter,
+};
+use crate::location::Location;
 use crate::map::Map;
-use serde::Deserialize;
+use crate::message::Message;
+use crate::message_category::MessageCategory;
+use crate::report::Response;
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Default)]
 pub struct RequestOptions {
@@ -199,52 +206,200 @@ pub enum DestinationOption {
     TahitiSydney,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FlightPlan {
     /// e.g. SUD EST FRANCE
-    #[serde(rename = "@id")]
+    #[serde(alias = "@id")]
     pub name: String,
 
     /// e.g. <https://aviation.meteo.fr/...>
-    #[serde(rename = "@lienPDF", deserialize_with = "de_option_link")]
+    #[serde(alias = "@lienPDF", deserialize_with = "de_option_link")]
     pub link: Option<String>,
 
-    #[serde(default, rename = "message")]
-    pub messages: Vec<Message>,
+    /// Messages grouped by location, in the order of the flight plan. A location appears several
+    /// times if its messages are not contiguous, e.g. the SIGMETs of a FIR after each airport.
+    #[serde(default, alias = "message", deserialize_with = "de_locations")]
+    pub locations: Vec<Location>,
 
-    #[serde(default, rename = "carte")]
+    #[serde(default, alias = "carte")]
     pub maps: Vec<Map>,
 
-    #[serde(default, rename = "VAG")]
+    #[serde(default, alias = "VAG")]
     pub vags: Vec<Center>,
 
-    #[serde(default, rename = "TCAG")]
+    #[serde(default, alias = "TCAG")]
     pub tcags: Vec<Center>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for FlightPlan {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Response for FlightPlan {
+    type Report = Location;
+
+    fn reports(&self) -> &[Location] {
+        &self.locations
+    }
+}
+
+impl FlightPlan {
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("dossier")
+                .with_attribute(("id", self.name.as_str()))
+                .with_attribute(("lienPDF", link_path(self.link.as_deref())))
+                .write_inner_content(|writer| {
+                    for entry in entries(&self.locations) {
+                        entry.write_xml(writer)?;
+                    }
+
+                    for map in &self.maps {
+                        map.write_xml(writer, true)?;
+                    }
+
+                    for vag in &self.vags {
+                        vag.write_xml(writer, "VAG")?;
+                    }
+
+                    for tcag in &self.tcags {
+                        tcag.write_xml(writer, "TCAG")?;
+                    }
+
+                    Ok(())
+                })?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Message of a flight plan as sent by the server, carrying its location
+#[derive(Debug, Clone)]
+struct Entry {
+    category: MessageCategory,
+    oaci: String,
+    name: String,
+    text: Option<String>,
 }
 
+impl Entry {
+    /// Writes the message as a `message` element
+    fn write_xml(&self, writer: &mut XmlWriter) -> std::io::Result<()> {
+        writer
+            .create_element("message")
+            .with_attribute(("type", self.category.to_string().as_str()))
+            .with_attribute(("oaci", self.oaci.as_str()))
+            .with_attribute(("nom", self.name.as_str()))
+            .write_inner_content(|writer| {
+                write_cdata(writer, "texte", self.text.as_deref(), "NIL")
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Returns the messages of the locations in the order of the flight plan, as sent by the server
+fn entries(locations: &[Location]) -> impl Iterator<Item = Entry> + '_ {
+    locations.iter().flat_map(|location| {
+        location.messages.iter().map(|message| Entry {
+            category: message.category.clone(),
+            oaci: location.oaci.clone(),
+            name: location.name.clone(),
+            text: message.text.clone(),
+        })
+    })
+}
+
+/// Message of a flight plan as sent by the server, carrying its location, or location with its
+/// messages as serialized by this library
 #[derive(Debug, Deserialize)]
-pub struct Message {
+struct Item {
     /// e.g. METAR, TAFL
-    #[serde(rename = "@type")]
-    pub category: String,
+    #[serde(default, alias = "@type")]
+    category: Option<MessageCategory>,
 
     /// e.g. LFTW, LFKS
-    #[serde(rename = "@oaci")]
-    pub oaci: String,
+    #[serde(alias = "@oaci")]
+    oaci: String,
 
     /// e.g. NIMES GARONS, SOLENZARA
-    #[serde(rename = "@nom")]
-    pub name: String,
+    #[serde(alias = "@nom")]
+    name: String,
 
     /// e.g. METAR LFTW 201530Z AUTO 04007KT 010V070 9999 -RA FEW032///\nSCT048/// BKN130/// ///CB 22/19 Q1015 BECMG NSC=
-    #[serde(default, rename = "texte", deserialize_with = "de_option_string")]
-    pub text: Option<String>,
+    #[serde(default, alias = "texte", deserialize_with = "de_option_string")]
+    text: Option<String>,
+
+    /// Messages of a serialized location
+    #[serde(default)]
+    messages: Option<Vec<Message>>,
+}
+
+/// Groups the contiguous messages of a location sent by the server, the serialized locations
+/// being kept as is.
+///
+/// # Errors
+///
+/// Returns an error if the messages cannot be parsed, e.g. if a message has no type.
+///
+fn de_locations<'de, D>(deserializer: D) -> Result<Vec<Location>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut locations: Vec<Location> = vec![];
+
+    for item in Vec::<Item>::deserialize(deserializer)? {
+        if let Some(messages) = item.messages {
+            locations.push(Location {
+                oaci: item.oaci,
+                name: item.name,
+                messages,
+            });
+            continue;
+        }
+
+        let message = Message {
+            category: item
+                .category
+                .ok_or_else(|| serde::de::Error::missing_field("category"))?,
+            reception_date: None,
+            text: item.text,
+        };
+
+        match locations.last_mut() {
+            Some(location) if location.oaci == item.oaci && location.name == item.name => {
+                location.messages.push(message);
+            }
+            _ => locations.push(Location {
+                oaci: item.oaci,
+                name: item.name,
+                messages: vec![message],
+            }),
+        }
+    }
+
+    Ok(locations)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::helpers::parse;
+    use crate::report::Report;
 
     #[test]
     fn test_flight_plan() {
@@ -255,22 +410,40 @@ mod tests {
 
         let data: FlightPlan = res.unwrap();
 
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["locations"][0]["oaci"], "LFBZ");
+        assert_eq!(json["locations"][0]["messages"][1]["category"], "TAFL");
+        assert_eq!(serde_json::from_value::<FlightPlan>(json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<FlightPlan>(&xml).unwrap(), data);
+
         assert_eq!(data.name, "GRAND SUD OUEST FRANCE");
         assert!(data.link.is_some());
 
-        assert_eq!(data.messages.len(), 56);
+        assert_eq!(
+            data.locations
+                .iter()
+                .map(|location| location.messages.len())
+                .sum::<usize>(),
+            56
+        );
 
-        let message = &data.messages[0];
-        assert_eq!(message.category, "METAR");
-        assert_eq!(message.oaci, "LFBZ");
-        assert_eq!(message.name, "BIARRITZ PAYS BASQUE");
-        assert!(message.text.is_some());
+        let location = &data.locations[0];
+        assert_eq!(location.oaci, "LFBZ");
+        assert_eq!(location.name, "BIARRITZ PAYS BASQUE");
+        assert_eq!(location.messages.len(), 2);
+        assert_eq!(location.messages[0].category, MessageCategory::Metar);
+        assert!(location.messages[0].text.is_some());
+        assert_eq!(location.messages[1].category, MessageCategory::Tafl);
+        assert!(location.messages[1].text.is_some());
 
-        let message2 = &data.messages[1];
-        assert_eq!(message2.category, "TAFL");
-        assert_eq!(message2.oaci, "LFBZ");
-        assert_eq!(message2.name, "BIARRITZ PAYS BASQUE");
-        assert!(message2.text.is_some());
+        let location2 = &data.locations[1];
+        assert_eq!(location2.oaci, "LFBB");
+        assert_eq!(location2.name, "BORDEAUX");
+        assert_eq!(location2.messages.len(), 2);
+        assert_eq!(location2.messages[0].category, MessageCategory::Sigmet);
+        assert_eq!(location2.latest_message(), location2.messages().next());
 
         assert_eq!(data.maps.len(), 4);
 
@@ -292,5 +465,9 @@ mod tests {
         assert!(vag.link.is_none());
 
         assert_eq!(data.tcags.len(), 0);
+
+        // A message without type is rejected rather than given an empty one
+        let xml = xml.replacen(r#"type="METAR" "#, "", 1);
+        assert!(parse::<FlightPlan>(&xml).is_err());
     }
 }
@@ -1,25 +1,81 @@
 use crate::airport::Airport;
-use crate::oaci_multiple::OaciMultiple;
-use serde::Deserialize;
+use crate::diff::Diff;
+use crate::error::Error;
+use crate::helpers::{write_document, Stale};
+use crate::icao::Icao;
+use crate::location::Location;
+use crate::report::Response;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RequestOptions {
     /// List of OACI codes of the airports
     /// e.g. `Airport::LFBO`, `Airport::LFBA`
     /// Maximum 50 airports
     pub airports: Vec<Airport>,
+
+    /// Other ICAO location indicators, e.g. `EGLL`, `KJFK`, counted in the limit of 50
+    pub locations: Vec<Icao>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Maa {
-    #[serde(default, rename = "messages")]
-    pub reports: Vec<OaciMultiple>,
+    #[serde(default, alias = "messages")]
+    pub reports: Vec<Location>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Maa {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Response for Maa {
+    type Report = Location;
+
+    fn reports(&self) -> &[Location] {
+        &self.reports
+    }
+}
+
+impl Maa {
+    /// Compares the response with a newer one, see `aeroweb::diff::Diff::between`
+    #[must_use]
+    pub fn diff(&self, newer: &Self) -> Diff {
+        Diff::between(self, newer)
+    }
+
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("groupe")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::helpers::parse;
+    use crate::message_category::MessageCategory;
+    use crate::report::Report;
 
     #[test]
     fn test_maa() {
@@ -30,19 +86,26 @@ mod tests {
 
         let data: Maa = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Maa>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Maa>(&xml).unwrap(), data);
+        assert!(xml.contains("<texte><![CDATA[NIL]]></texte>"));
+
         assert_eq!(data.reports.len(), 5);
 
         let report = &data.reports[0];
         assert_eq!(report.oaci, "LFLY");
         assert_eq!(report.name, "LYON BRON");
         assert_eq!(report.messages.len(), 3);
-        assert_eq!(report.messages[0].category, "MAA");
+        assert_eq!(report.messages[0].category, MessageCategory::Maa);
         assert_eq!(
             report.messages[0].reception_date,
             Some(String::from("20240715124000"))
         );
         assert!(report.messages[0].text.is_some());
-        assert_eq!(report.messages[1].category, "MAA");
+        assert_eq!(report.messages[1].category, MessageCategory::Maa);
         assert_eq!(
             report.messages[1].reception_date,
             Some(String::from("20240715124000"))
@@ -53,12 +116,16 @@ mod tests {
         assert_eq!(report2.oaci, "LFPG");
         assert_eq!(report2.name, "PARIS CHARLES DE GAULLE");
         assert_eq!(report2.messages.len(), 3);
+        assert_eq!(
+            report2.latest_message().unwrap().reception_date,
+            Some(String::from("20240715104200"))
+        );
 
         let report3 = &data.reports[2];
         assert_eq!(report3.oaci, "LFBO");
         assert_eq!(report3.name, "TOULOUSE BLAGNAC");
         assert_eq!(report3.messages.len(), 1);
-        assert_eq!(report3.messages[0].category, "MAA");
+        assert_eq!(report3.messages[0].category, MessageCategory::Maa);
         assert!(report3.messages[0].reception_date.is_none());
         assert!(report3.messages[0].text.is_none());
     }
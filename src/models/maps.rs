@@ -1,5 +1,7 @@
+use crate::error::Error;
+use crate::helpers::{write_document, Stale, XmlWriter};
 use crate::map::Map;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default)]
 pub struct RequestOptions {
@@ -70,7 +72,7 @@ pub enum LevelOption {
     FL530,
 }
 
-#[derive(Debug, Default, strum::Display)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, strum::Display)]
 pub enum ZoneOption {
     #[default]
     #[strum(serialize = "AERO_FRANCE")]
@@ -157,26 +159,75 @@ pub enum ZoneOption {
     SouthPol,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Maps {
-    #[serde(default, rename = "bloc_zone")]
+    #[serde(default, alias = "bloc_zone")]
     pub zones: Vec<Zone>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Maps {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl Maps {
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("cartes")
+                .write_inner_content(|writer| {
+                    self.zones
+                        .iter()
+                        .try_for_each(|zone| zone.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Zone {
     /// e.g. FRANCE, EUROC
-    #[serde(rename = "@idz")]
+    #[serde(alias = "@idz")]
     pub id: String,
 
     /// e.g. FRANCE, EUROC
-    #[serde(rename = "@nom")]
+    #[serde(alias = "@nom")]
     pub name: String,
 
-    #[serde(default, rename = "carte")]
+    #[serde(default, alias = "carte")]
     pub maps: Vec<Map>,
 }
 
+impl Zone {
+    /// Writes the zone and its maps as a `bloc_zone` element
+    fn write_xml(&self, writer: &mut XmlWriter) -> std::io::Result<()> {
+        writer
+            .create_element("bloc_zone")
+            .with_attribute(("idz", self.id.as_str()))
+            .with_attribute(("nom", self.name.as_str()))
+            .write_inner_content(|writer| {
+                self.maps
+                    .iter()
+                    .try_for_each(|map| map.write_xml(writer, false))
+            })?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,6 +242,12 @@ mod tests {
 
         let data: Maps = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Maps>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Maps>(&xml).unwrap(), data);
+
         assert_eq!(data.zones.len(), 2);
 
         let zone = &data.zones[0];
@@ -1,9 +1,11 @@
+pub mod briefing;
 pub mod flight_plan;
 pub mod maa;
 pub mod maps;
 pub mod opmet;
 pub mod predec;
 pub mod sigmet;
+pub mod snapshot;
 pub mod sw;
 pub mod tca;
 pub mod tcag;
@@ -1,50 +1,154 @@
 use crate::airport::Airport;
-use crate::helpers::de_option_string;
-use serde::Deserialize;
-
-#[derive(Debug)]
+use crate::diff::Diff;
+use crate::error::Error;
+use crate::helpers::{de_option_string, write_cdata, write_document, Stale, XmlWriter};
+use crate::icao::Icao;
+use crate::message::Message;
+use crate::message_category::MessageCategory;
+use crate::report::{Report, Response};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default)]
 pub struct RequestOptions {
     /// List of OACI codes of the airports
     /// e.g. `Airport::LFBO`, `Airport::LFBA`
     /// Maximum 50 airports
     pub airports: Vec<Airport>,
+
+    /// Other ICAO location indicators, e.g. `EGLL`, `KJFK`, counted in the limit of 50
+    pub locations: Vec<Icao>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Opmet {
-    #[serde(default, rename = "opmet")]
+    #[serde(default, alias = "opmet")]
     pub reports: Vec<Data>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Opmet {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Response for Opmet {
+    type Report = Data;
+
+    fn reports(&self) -> &[Data] {
+        &self.reports
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl Opmet {
+    /// Compares the response with a newer one, see `aeroweb::diff::Diff::between`
+    #[must_use]
+    pub fn diff(&self, newer: &Self) -> Diff {
+        Diff::between(self, newer)
+    }
+
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("root")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Data {
     /// e.g. LFBO, LFBA
-    #[serde(rename = "@oaci")]
+    #[serde(alias = "@oaci")]
     pub oaci: String,
 
     /// e.g. TOULOUSE BLAGNAC, AGEN LA GARENNE
-    #[serde(rename = "@nom")]
+    #[serde(alias = "@nom")]
     pub name: String,
 
-    #[serde(rename = "METAR", deserialize_with = "de_option_string")]
+    #[serde(alias = "METAR", deserialize_with = "de_option_string")]
     pub metar: Option<String>,
 
-    #[serde(rename = "TAF", deserialize_with = "de_option_string")]
+    #[serde(alias = "TAF", deserialize_with = "de_option_string")]
     pub taf: Option<String>,
 
-    #[serde(rename = "SPECI", deserialize_with = "de_option_string")]
+    #[serde(alias = "SPECI", deserialize_with = "de_option_string")]
     pub speci: Option<String>,
 
-    #[serde(rename = "SIGMET", deserialize_with = "de_option_string")]
+    #[serde(alias = "SIGMET", deserialize_with = "de_option_string")]
     pub sigmet: Option<String>,
 
-    #[serde(rename = "GAMET", deserialize_with = "de_option_string")]
+    #[serde(alias = "GAMET", deserialize_with = "de_option_string")]
     pub gamet: Option<String>,
 
-    #[serde(rename = "AIRMET", deserialize_with = "de_option_string")]
+    #[serde(alias = "AIRMET", deserialize_with = "de_option_string")]
     pub airmet: Option<String>,
 }
 
+impl Data {
+    /// Writes the reports of the airport as an `opmet` element
+    fn write_xml(&self, writer: &mut XmlWriter) -> std::io::Result<()> {
+        writer
+            .create_element("opmet")
+            .with_attribute(("oaci", self.oaci.as_str()))
+            .with_attribute(("nom", self.name.as_str()))
+            .write_inner_content(|writer| {
+                write_cdata(writer, "METAR", self.metar.as_deref(), "NODATA")?;
+                write_cdata(writer, "TAF", self.taf.as_deref(), "NODATA")?;
+                write_cdata(writer, "SPECI", self.speci.as_deref(), "NODATA")?;
+                write_cdata(writer, "SIGMET", self.sigmet.as_deref(), "NODATA")?;
+                write_cdata(writer, "GAMET", self.gamet.as_deref(), "NODATA")?;
+                write_cdata(writer, "AIRMET", self.airmet.as_deref(), "NODATA")
+            })?;
+
+        Ok(())
+    }
+}
+
+impl Report for Data {
+    fn oaci(&self) -> &str {
+        &self.oaci
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn messages(&self) -> impl Iterator<Item = Message> + '_ {
+        [
+            (MessageCategory::Metar, &self.metar),
+            (MessageCategory::Taf, &self.taf),
+            (MessageCategory::Speci, &self.speci),
+            (MessageCategory::Sigmet, &self.sigmet),
+            (MessageCategory::Gamet, &self.gamet),
+            (MessageCategory::Airmet, &self.airmet),
+        ]
+        .into_iter()
+        .filter_map(|(category, text)| {
+            Some(Message {
+                category,
+                reception_date: None,
+                text: Some(text.clone()?),
+            })
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,6 +163,13 @@ mod tests {
 
         let data: Opmet = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Opmet>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Opmet>(&xml).unwrap(), data);
+        assert!(xml.contains("<SPECI><![CDATA[NODATA]]></SPECI>"));
+
         assert_eq!(data.reports.len(), 2);
 
         let report = &data.reports[0];
@@ -70,6 +181,17 @@ mod tests {
         assert!(report.sigmet.is_some());
         assert!(report.gamet.is_none());
         assert!(report.airmet.is_none());
+        assert_eq!(
+            report
+                .messages()
+                .map(|message| message.category)
+                .collect::<Vec<_>>(),
+            [
+                MessageCategory::Metar,
+                MessageCategory::Taf,
+                MessageCategory::Sigmet
+            ]
+        );
 
         let report2 = &data.reports[1];
         assert_eq!(report2.oaci, "LFBA");
@@ -1,13 +1,34 @@
-use crate::oaci::Oaci;
-use serde::Deserialize;
+use crate::error::Error;
+use crate::helpers::{ord_by_code, serde_from_str, write_document, Stale};
+use crate::icao::Icao;
+use crate::location::Location;
+use crate::report::Response;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RequestOptions {
     /// List of OACI codes of airports emitting PREDEC
     pub airports: Vec<AirportOption>,
+
+    /// Other ICAO location indicators of airports emitting PREDEC, not yet listed in
+    /// `AirportOption`
+    pub locations: Vec<Icao>,
 }
 
-#[derive(Debug, strum::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::Display,
+    strum::EnumCount,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::IntoStaticStr,
+)]
+#[strum(ascii_case_insensitive)]
 pub enum AirportOption {
     /// CDG
     LFPG,
@@ -27,16 +48,85 @@ pub enum AirportOption {
     NTAA,
 }
 
-#[derive(Debug, Deserialize)]
+serde_from_str!(AirportOption);
+ord_by_code!(AirportOption);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Predec {
-    #[serde(default, rename = "messages")]
-    pub reports: Vec<Oaci>,
+    #[serde(default, alias = "messages")]
+    pub reports: Vec<Location>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Predec {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Response for Predec {
+    type Report = Location;
+
+    fn reports(&self) -> &[Location] {
+        &self.reports
+    }
+}
+
+impl Predec {
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("groupe")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::helpers::parse;
+    use crate::message_category::MessageCategory;
+
+    #[test]
+    fn test_airport_option() {
+        assert_eq!(
+            "LFPG".parse::<AirportOption>().unwrap(),
+            AirportOption::LFPG
+        );
+        assert_eq!(
+            "ntaa".parse::<AirportOption>().unwrap(),
+            AirportOption::NTAA
+        );
+        assert!("LFBO".parse::<AirportOption>().is_err());
+
+        assert_eq!(
+            serde_json::to_string(&AirportOption::LFPG).unwrap(),
+            "\"LFPG\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Vec<AirportOption>>("[\"LFPG\", \"soca\"]").unwrap(),
+            [AirportOption::LFPG, AirportOption::SOCA]
+        );
+        assert!(serde_json::from_str::<AirportOption>("\"LFBO\"").is_err());
+
+        assert!(AirportOption::FMEE < AirportOption::LFPG);
+    }
 
     #[test]
     fn test_predec() {
@@ -47,33 +137,39 @@ mod tests {
 
         let data: Predec = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Predec>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Predec>(&xml).unwrap(), data);
+
         assert_eq!(data.reports.len(), 5);
 
         let report = &data.reports[0];
         assert_eq!(report.oaci, "LFPO");
         assert_eq!(report.name, "PARIS ORLY");
-        assert_eq!(report.message.category, String::from("PREDEC"));
+        assert_eq!(report.messages[0].category, MessageCategory::Predec);
         assert_eq!(
-            report.message.reception_date,
+            report.messages[0].reception_date,
             Some(String::from("20240423201500"))
         );
-        assert!(report.message.text.is_some());
+        assert!(report.messages[0].text.is_some());
 
         let report2 = &data.reports[1];
         assert_eq!(report2.oaci, "LFPG");
         assert_eq!(report2.name, "PARIS CHARLES DE GAULLE");
-        assert_eq!(report2.message.category, String::from("PREDEC"));
+        assert_eq!(report2.messages[0].category, MessageCategory::Predec);
         assert_eq!(
-            report2.message.reception_date,
+            report2.messages[0].reception_date,
             Some(String::from("20240423201500"))
         );
-        assert!(report2.message.text.is_some());
+        assert!(report2.messages[0].text.is_some());
 
         let report3 = &data.reports[2];
         assert_eq!(report3.oaci, "SOCA");
         assert_eq!(report3.name, "CAYENNE FELIX EBOUE");
-        assert_eq!(report3.message.category, String::from("PREDEC"));
-        assert!(report3.message.reception_date.is_none());
-        assert!(report3.message.text.is_none());
+        assert_eq!(report3.messages[0].category, MessageCategory::Predec);
+        assert!(report3.messages[0].reception_date.is_none());
+        assert!(report3.messages[0].text.is_none());
     }
 }
@@ -1,10 +1,18 @@
 use crate::airport::Airport;
+use crate::diff::Diff;
+use crate::error::Error;
 use crate::fir::Fir;
-use crate::helpers::de_option_string;
-use serde::Deserialize;
+use crate::geodata::Geodata;
+use crate::hazard::Hazard;
+use crate::helpers::{de_option_string, write_cdata, write_document, Stale, XmlWriter};
+use crate::icao::Icao;
+use crate::message::Message;
+use crate::message_category::MessageCategory;
+use crate::report::{Report, Response};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
-/// Maximum of 50 airports and FIRs combined
+#[derive(Debug, Default)]
+/// Maximum of 50 airports, FIRs and locations combined
 pub struct RequestOptions {
     /// List of OACI codes of the airports
     /// e.g. `Airport::LFBO`, `Airport::LFBA`
@@ -13,38 +21,214 @@ pub struct RequestOptions {
     /// List of OACI codes of the Flight Information Regions
     /// e.g. `Fir::LFBB`, `Fir::EBBU`
     pub firs: Vec<Fir>,
+
+    /// Other ICAO location indicators, e.g. `EGLL`, `KJFK`, counted in the limit of 50
+    pub locations: Vec<Icao>,
 }
 
-#[derive(Debug, Deserialize)]
+impl RequestOptions {
+    /// Maximum number of airports, FIRs and locations in a single request
+    pub const LIMIT: usize = 50;
+
+    /// Builds the requests covering the airports along with the FIRs containing them, split so
+    /// that each request respects the limit of 50 locations
+    /// e.g. `RequestOptions::for_airports(&[Airport::LFBO, Airport::LFPG], &geodata)`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the FIR of an airport is unknown, see `aeroweb::geodata::Geodata::fir`.
+    ///
+    pub fn for_airports(
+        airports: &[Airport],
+        geodata: &Geodata,
+    ) -> Result<Vec<RequestOptions>, Error> {
+        let mut requests = Vec::<RequestOptions>::new();
+
+        for &airport in airports {
+            if requests
+                .iter()
+                .any(|request| request.airports.contains(&airport))
+            {
+                continue;
+            }
+
+            let fir = geodata.require_fir(airport)?;
+
+            match requests.last_mut() {
+                Some(request)
+                    if request.airports.len()
+                        + request.firs.len()
+                        + usize::from(!request.firs.contains(&fir))
+                        < RequestOptions::LIMIT =>
+                {
+                    request.airports.push(airport);
+
+                    if !request.firs.contains(&fir) {
+                        request.firs.push(fir);
+                    }
+                }
+                _ => requests.push(RequestOptions {
+                    airports: vec![airport],
+                    firs: vec![fir],
+                    locations: vec![],
+                }),
+            }
+        }
+
+        Ok(requests)
+    }
+
+    /// Builds the requests covering the FIRs, split so that each request respects the limit of
+    /// 50 locations
+    /// e.g. `RequestOptions::for_firs(&Fir::of_region(IcaoRegion::Europe))`
+    #[must_use]
+    pub fn for_firs(firs: &[Fir]) -> Vec<RequestOptions> {
+        let mut unique = Vec::with_capacity(firs.len());
+
+        for &fir in firs {
+            if !unique.contains(&fir) {
+                unique.push(fir);
+            }
+        }
+
+        unique
+            .chunks(RequestOptions::LIMIT)
+            .map(|firs| RequestOptions {
+                firs: firs.to_vec(),
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Sigmet {
-    #[serde(default, rename = "FIR")]
+    #[serde(default, alias = "FIR")]
     pub reports: Vec<Data>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Sigmet {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Response for Sigmet {
+    type Report = Data;
+
+    fn reports(&self) -> &[Data] {
+        &self.reports
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl Sigmet {
+    /// Compares the response with a newer one, see `aeroweb::diff::Diff::between`
+    #[must_use]
+    pub fn diff(&self, newer: &Self) -> Diff {
+        Diff::between(self, newer)
+    }
+
+    /// Returns the hazards decoded from the messages, the SIGMETs and AIRMETs issued for the
+    /// entire FIR taking the boundary loaded in `geodata`, see `aeroweb::hazard::Hazard::parse`
+    #[must_use]
+    pub fn hazards(&self, geodata: &Geodata) -> Vec<Hazard> {
+        Hazard::from_reports(&self.reports, Some(geodata))
+    }
+
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("root")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Data {
     /// e.g. LFMM, EBBU
-    #[serde(rename = "@oaci")]
+    #[serde(alias = "@oaci")]
     pub oaci: String,
 
     /// e.g. MARSEILLE, BRUSSELS
-    #[serde(rename = "@nom")]
+    #[serde(alias = "@nom")]
     pub name: String,
 
-    #[serde(rename = "SIGMET", deserialize_with = "de_option_string")]
+    #[serde(alias = "SIGMET", deserialize_with = "de_option_string")]
     pub sigmet: Option<String>,
 
-    #[serde(rename = "GAMET", deserialize_with = "de_option_string")]
+    #[serde(alias = "GAMET", deserialize_with = "de_option_string")]
     pub gamet: Option<String>,
 
-    #[serde(rename = "AIRMET", deserialize_with = "de_option_string")]
+    #[serde(alias = "AIRMET", deserialize_with = "de_option_string")]
     pub airmet: Option<String>,
 }
 
+impl Data {
+    /// Writes the reports of the FIR as a `FIR` element
+    fn write_xml(&self, writer: &mut XmlWriter) -> std::io::Result<()> {
+        writer
+            .create_element("FIR")
+            .with_attribute(("oaci", self.oaci.as_str()))
+            .with_attribute(("nom", self.name.as_str()))
+            .write_inner_content(|writer| {
+                write_cdata(writer, "SIGMET", self.sigmet.as_deref(), "NODATA")?;
+                write_cdata(writer, "GAMET", self.gamet.as_deref(), "NODATA")?;
+                write_cdata(writer, "AIRMET", self.airmet.as_deref(), "NODATA")
+            })?;
+
+        Ok(())
+    }
+}
+
+impl Report for Data {
+    fn oaci(&self) -> &str {
+        &self.oaci
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn messages(&self) -> impl Iterator<Item = Message> + '_ {
+        [
+            (MessageCategory::Sigmet, &self.sigmet),
+            (MessageCategory::Gamet, &self.gamet),
+            (MessageCategory::Airmet, &self.airmet),
+        ]
+        .into_iter()
+        .filter_map(|(category, text)| {
+            Some(Message {
+                category,
+                reception_date: None,
+                text: Some(text.clone()?),
+            })
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hazard::HazardKind;
     use crate::helpers::parse;
+    use strum::IntoEnumIterator;
 
     #[test]
     fn test_sigmet() {
@@ -55,8 +239,19 @@ mod tests {
 
         let data: Sigmet = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Sigmet>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Sigmet>(&xml).unwrap(), data);
+
         assert_eq!(data.reports.len(), 4);
 
+        let hazards = data.hazards(&Geodata::new());
+        assert_eq!(hazards.len(), 1);
+        assert_eq!(hazards[0].location, "LFMM");
+        assert_eq!(hazards[0].kind, HazardKind::Sigmet);
+
         let report = &data.reports[0];
         assert_eq!(report.oaci, "LFMM");
         assert_eq!(report.name, "MARSEILLE");
@@ -77,5 +272,66 @@ mod tests {
         assert!(report3.sigmet.is_none());
         assert!(report3.gamet.is_none());
         assert!(report3.airmet.is_none());
+
+        let geodata = Geodata::fixture();
+
+        let requests =
+            RequestOptions::for_airports(&[Airport::LFBO, Airport::LFBA, Airport::LFBO], &geodata)
+                .unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].airports, [Airport::LFBO, Airport::LFBA]);
+        assert_eq!(requests[0].firs, [Fir::LFBB]);
+
+        // Every airport of the enum, at the positions of the loaded ones in turn
+        let loaded = Airport::iter()
+            .filter_map(|airport| geodata.position(airport))
+            .collect::<Vec<_>>();
+        let positions = Airport::iter()
+            .zip(loaded.into_iter().cycle())
+            .map(|(airport, position)| {
+                let coordinates = position.coordinates;
+                format!(
+                    "{airport},{},{},",
+                    coordinates.latitude, coordinates.longitude
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut everywhere = Geodata::fixture();
+        everywhere
+            .load_airports(&format!(
+                "ident,latitude_deg,longitude_deg,elevation_ft\n{}",
+                positions.join("\n")
+            ))
+            .unwrap();
+        let airports = Airport::iter().collect::<Vec<_>>();
+        let requests = RequestOptions::for_airports(&airports, &everywhere).unwrap();
+        assert!(requests.iter().all(|request| {
+            request.airports.len() + request.firs.len() <= RequestOptions::LIMIT
+                && request
+                    .airports
+                    .iter()
+                    .all(|&airport| request.firs.contains(&everywhere.fir(airport).unwrap()))
+        }));
+        assert_eq!(
+            requests
+                .iter()
+                .map(|request| request.airports.len())
+                .sum::<usize>(),
+            airports.len()
+        );
+
+        assert!(matches!(
+            RequestOptions::for_airports(&[Airport::LFBO, Airport::LFHN], &geodata),
+            Err(Error::InvalidGeodata(_))
+        ));
+
+        let requests = RequestOptions::for_firs(&Fir::iter().collect::<Vec<_>>());
+        assert_eq!(requests.len(), 6);
+        assert_eq!(requests[5].firs.len(), 50);
+        assert!(requests.iter().all(|request| request.airports.is_empty()));
+        assert_eq!(
+            RequestOptions::for_firs(&[Fir::LFBB, Fir::LFBB])[0].firs,
+            [Fir::LFBB]
+        );
     }
 }
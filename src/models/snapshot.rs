@@ -0,0 +1,211 @@
+use crate::airport::Airport;
+use crate::error::Error;
+use crate::icao::Icao;
+use crate::location::Location;
+#[cfg(feature = "client")]
+use crate::report::{Report, Response};
+use serde::{Serialize, Serializer};
+use std::sync::Arc;
+
+#[derive(Debug, Default)]
+pub struct RequestOptions {
+    /// List of OACI codes of the airports
+    /// e.g. `Airport::LFBO`, `Airport::LFBA`
+    pub airports: Vec<Airport>,
+
+    /// Other ICAO location indicators, e.g. `EGLL`, `KJFK`, only briefed with OPMET
+    pub locations: Vec<Icao>,
+}
+
+impl RequestOptions {
+    /// Returns the locations of the snapshot, the airports first, each one once
+    #[must_use]
+    pub fn all_locations(&self) -> Vec<Icao> {
+        let mut locations = Vec::<Icao>::new();
+
+        for location in self
+            .airports
+            .iter()
+            .map(|&airport| Icao::from(airport))
+            .chain(self.locations.iter().cloned())
+        {
+            if !locations.contains(&location) {
+                locations.push(location);
+            }
+        }
+
+        locations
+    }
+}
+
+/// Outcome of the request of a product for a location
+#[derive(Debug, Clone)]
+pub enum Outcome<T> {
+    /// The product is not available for this location, it was not requested
+    Unsupported,
+
+    /// The server sent nothing for this location
+    Empty,
+
+    Ok(T),
+
+    /// The request failed, the error being shared by all the locations of the request
+    Failed(Arc<Error>),
+}
+
+impl<T> Outcome<T> {
+    /// Returns the data if the request succeeded and the server sent some
+    #[must_use]
+    pub fn ok(&self) -> Option<&T> {
+        match self {
+            Outcome::Ok(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns the error if the request failed
+    #[must_use]
+    pub fn error(&self) -> Option<&Error> {
+        match self {
+            Outcome::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Serialized as `"Unsupported"`, `"Empty"`, `{"Ok": data}` or `{"Failed": "message"}`, the
+/// error being replaced by its message
+impl<T: Serialize> Serialize for Outcome<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Outcome::Unsupported => serializer.serialize_unit_variant("Outcome", 0, "Unsupported"),
+            Outcome::Empty => serializer.serialize_unit_variant("Outcome", 1, "Empty"),
+            Outcome::Ok(data) => serializer.serialize_newtype_variant("Outcome", 2, "Ok", data),
+            Outcome::Failed(error) => {
+                serializer.serialize_newtype_variant("Outcome", 3, "Failed", &error.to_string())
+            }
+        }
+    }
+}
+
+impl<T, E: Into<Arc<Error>>> From<Result<T, E>> for Outcome<T> {
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(data) => Outcome::Ok(data),
+            Err(error) => Outcome::Failed(error.into()),
+        }
+    }
+}
+
+/// Response of one of the requests of a product, along with the locations it was sent for
+#[cfg(feature = "client")]
+#[derive(Debug)]
+pub(crate) struct Batch<R> {
+    pub(crate) locations: Vec<String>,
+    pub(crate) response: Result<R, Arc<Error>>,
+}
+
+#[cfg(feature = "client")]
+impl<R: Response> Batch<R>
+where
+    R::Report: Clone,
+{
+    pub(crate) fn new<T: ToString>(locations: &[T], response: Result<R, Error>) -> Batch<R> {
+        Batch {
+            locations: locations.iter().map(ToString::to_string).collect(),
+            response: response.map_err(Arc::new),
+        }
+    }
+
+    /// Returns the outcome for a location among the responses of a product
+    pub(crate) fn find(batches: &[Batch<R>], location: &str) -> Outcome<R::Report> {
+        let Some(batch) = batches
+            .iter()
+            .find(|batch| batch.locations.iter().any(|code| code == location))
+        else {
+            return Outcome::Unsupported;
+        };
+
+        match &batch.response {
+            Ok(response) => response
+                .reports()
+                .iter()
+                .find(|report| report.oaci() == location)
+                .map_or(Outcome::Empty, |report| Outcome::Ok(report.clone())),
+            Err(error) => Outcome::Failed(Arc::clone(error)),
+        }
+    }
+}
+
+/// Latest products of a set of airports, retrieved at once
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    /// One snapshot per location, in the order of the request
+    pub airports: Vec<AirportSnapshot>,
+
+    /// Space weather advisories, which apply to all the locations
+    pub space_weather: Outcome<crate::sw::SpaceWeather>,
+
+    /// `true` if one of the responses was served from the disk cache because the API could not
+    /// be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AirportSnapshot {
+    pub location: Icao,
+
+    /// METAR, TAF, ... of the location
+    pub opmet: Outcome<crate::opmet::Data>,
+
+    /// Aerodrome warnings, only for French airports
+    pub maa: Outcome<Location>,
+
+    /// Take-off forecasts, only for the airports of `predec::AirportOption`
+    pub predec: Outcome<Location>,
+
+    /// SIGMET, AIRMET and GAMET of the FIR of the airport, only for French airports
+    pub sigmet: Outcome<crate::sigmet::Data>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let options = RequestOptions {
+            airports: vec![Airport::LFBO, Airport::LFBA],
+            locations: vec!["EGLL".parse().unwrap(), "lfbo".parse().unwrap()],
+        };
+        assert_eq!(
+            options
+                .all_locations()
+                .iter()
+                .map(Icao::as_str)
+                .collect::<Vec<_>>(),
+            ["LFBO", "LFBA", "EGLL"]
+        );
+
+        #[cfg(feature = "client")]
+        {
+            let data = std::fs::read_to_string("./data/opmet2.xml").unwrap();
+            let batches = [
+                Batch::<crate::opmet::Opmet>::new(&["LFBO", "LFPG"], crate::parse(&data)),
+                Batch::new(&["EGLL"], Err(Error::InvalidOptions(String::new()))),
+            ];
+
+            assert_eq!(Batch::find(&batches, "LFBO").ok().unwrap().oaci, "LFBO");
+            assert!(matches!(Batch::find(&batches, "LFPG"), Outcome::Empty));
+            assert!(matches!(
+                Batch::find(&batches, "EGLL").error(),
+                Some(Error::InvalidOptions(_))
+            ));
+            assert!(matches!(
+                Batch::find(&batches, "LFBA"),
+                Outcome::Unsupported
+            ));
+        }
+    }
+}
@@ -1,27 +1,103 @@
-use crate::helpers::de_option_string;
-use serde::Deserialize;
+use crate::error::Error;
+use crate::helpers::{de_option_string, write_cdata_element, write_document, Stale, XmlWriter};
+use crate::message::Message;
+use crate::message_category::MessageCategory;
+use crate::report::{Report, Response};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SpaceWeather {
-    #[serde(default, rename = "SPACEWEATHER")]
+    #[serde(default, alias = "SPACEWEATHER")]
     pub reports: Vec<Data>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for SpaceWeather {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
 }
 
-#[derive(Debug, Deserialize)]
+impl Response for SpaceWeather {
+    type Report = Data;
+
+    fn reports(&self) -> &[Data] {
+        &self.reports
+    }
+}
+
+impl SpaceWeather {
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("root")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Data {
     /// e.g. KWNP, EFKL
-    #[serde(rename = "@ID")]
+    #[serde(alias = "@ID")]
     pub oaci: String,
 
     /// e.g. NOAA/SWPC, PECASUS
-    #[serde(rename = "@NAME")]
+    #[serde(alias = "@NAME")]
     pub name: String,
 
     /// e.g. SWX ADVISORY ...
-    #[serde(rename = "$text", deserialize_with = "de_option_string")]
+    #[serde(alias = "$text", deserialize_with = "de_option_string")]
     pub text: Option<String>,
 }
 
+impl Data {
+    /// Writes the advisory as a `SPACEWEATHER` element
+    fn write_xml(&self, writer: &mut XmlWriter) -> std::io::Result<()> {
+        write_cdata_element(
+            writer
+                .create_element("SPACEWEATHER")
+                .with_attribute(("ID", self.oaci.as_str()))
+                .with_attribute(("NAME", self.name.as_str())),
+            self.text.as_deref(),
+            "NODATA",
+        )
+    }
+}
+
+impl Report for Data {
+    fn oaci(&self) -> &str {
+        &self.oaci
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn messages(&self) -> impl Iterator<Item = Message> + '_ {
+        self.text.iter().map(|text| Message {
+            category: MessageCategory::Swx,
+            reception_date: None,
+            text: Some(text.clone()),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,6 +112,12 @@ mod tests {
 
         let data: SpaceWeather = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<SpaceWeather>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<SpaceWeather>(&xml).unwrap(), data);
+
         assert_eq!(data.reports.len(), 7);
 
         let report = &data.reports[0];
@@ -47,5 +129,11 @@ mod tests {
         assert_eq!(report2.oaci, "LFPW");
         assert_eq!(report2.name, "ACFJ/SPECTRA");
         assert!(report2.text.is_none());
+
+        // "]]>" cannot be written as is in a CDATA section
+        let mut data = data;
+        data.reports[0].text = Some("SWX ADVISORY ]]> END".to_string());
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<SpaceWeather>(&xml).unwrap(), data);
     }
 }
@@ -1,13 +1,34 @@
-use crate::oaci::Oaci;
-use serde::Deserialize;
+use crate::error::Error;
+use crate::hazard::Hazard;
+use crate::helpers::{ord_by_code, serde_from_str, write_document, Stale};
+use crate::icao::Icao;
+use crate::location::Location;
+use crate::report::Response;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RequestOptions {
     /// List of OACI codes of producing centers
     pub airports: Vec<AirportOption>,
+
+    /// Other ICAO location indicators of producing centers
+    pub locations: Vec<Icao>,
 }
 
-#[derive(Debug, strum::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::Display,
+    strum::EnumCount,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::IntoStaticStr,
+)]
+#[strum(ascii_case_insensitive)]
 pub enum AirportOption {
     /// La Réunion
     FMEE,
@@ -25,16 +46,66 @@ pub enum AirportOption {
     ADRM,
 }
 
-#[derive(Debug, Deserialize)]
+serde_from_str!(AirportOption);
+ord_by_code!(AirportOption);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tca {
-    #[serde(default, rename = "messages")]
-    pub reports: Vec<Oaci>,
+    #[serde(default, alias = "messages")]
+    pub reports: Vec<Location>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Tca {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Response for Tca {
+    type Report = Location;
+
+    fn reports(&self) -> &[Location] {
+        &self.reports
+    }
+}
+
+impl Tca {
+    /// Returns the hazards decoded from the messages, see `aeroweb::hazard::Hazard::parse`
+    #[must_use]
+    pub fn hazards(&self) -> Vec<Hazard> {
+        Hazard::from_reports(&self.reports, None)
+    }
+
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("groupe")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::helpers::parse;
+    use crate::message_category::MessageCategory;
 
     #[test]
     fn test_tca() {
@@ -45,20 +116,28 @@ mod tests {
 
         let data: Tca = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Tca>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Tca>(&xml).unwrap(), data);
+
         assert_eq!(data.reports.len(), 7);
 
+        assert!(data.hazards().is_empty());
+
         let report = &data.reports[0];
         assert_eq!(report.oaci, "FMEE");
         assert_eq!(report.name, "LA REUNION");
-        assert_eq!(report.message.category, String::from("TCA"));
-        assert!(report.message.reception_date.is_none());
-        assert!(report.message.text.is_none());
+        assert_eq!(report.messages[0].category, MessageCategory::Tca);
+        assert!(report.messages[0].reception_date.is_none());
+        assert!(report.messages[0].text.is_none());
 
         let report2 = &data.reports[1];
         assert_eq!(report2.oaci, "KNHC");
         assert_eq!(report2.name, "MIAMI");
-        assert_eq!(report2.message.category, String::from("TCA"));
-        assert!(report2.message.reception_date.is_none());
-        assert!(report2.message.text.is_none());
+        assert_eq!(report2.messages[0].category, MessageCategory::Tca);
+        assert!(report2.messages[0].reception_date.is_none());
+        assert!(report2.messages[0].text.is_none());
     }
 }
@@ -1,13 +1,34 @@
-use crate::oaci_multiple::OaciMultiple;
-use serde::Deserialize;
+use crate::error::Error;
+use crate::hazard::Hazard;
+use crate::helpers::{ord_by_code, serde_from_str, write_document, Stale};
+use crate::icao::Icao;
+use crate::location::Location;
+use crate::report::Response;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RequestOptions {
     /// List of OACI codes of producing centers
     pub airports: Vec<AirportOption>,
+
+    /// Other ICAO location indicators of producing centers
+    pub locations: Vec<Icao>,
 }
 
-#[derive(Debug, strum::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::Display,
+    strum::EnumCount,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::IntoStaticStr,
+)]
+#[strum(ascii_case_insensitive)]
 pub enum AirportOption {
     /// Anchorage
     PAWU,
@@ -29,16 +50,67 @@ pub enum AirportOption {
     NZKL,
 }
 
-#[derive(Debug, Deserialize)]
+serde_from_str!(AirportOption);
+ord_by_code!(AirportOption);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vaa {
-    #[serde(default, rename = "messages")]
-    pub reports: Vec<OaciMultiple>,
+    #[serde(default, alias = "messages")]
+    pub reports: Vec<Location>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Vaa {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Response for Vaa {
+    type Report = Location;
+
+    fn reports(&self) -> &[Location] {
+        &self.reports
+    }
+}
+
+impl Vaa {
+    /// Returns the hazards decoded from the messages, see `aeroweb::hazard::Hazard::parse`
+    #[must_use]
+    pub fn hazards(&self) -> Vec<Hazard> {
+        Hazard::from_reports(&self.reports, None)
+    }
+
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("groupe")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer))
+                })?;
+
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hazard::HazardKind;
     use crate::helpers::parse;
+    use crate::message_category::MessageCategory;
 
     #[test]
     fn test_vaa() {
@@ -49,14 +121,26 @@ mod tests {
 
         let data: Vaa = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Vaa>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Vaa>(&xml).unwrap(), data);
+
         assert_eq!(data.reports.len(), 2);
 
+        let hazards = data.hazards();
+        assert!(hazards
+            .iter()
+            .all(|hazard| hazard.kind == HazardKind::VolcanicAsh && hazard.location == "ADRM"));
+        assert_eq!(hazards[0].valid_from.as_deref(), Some("081850"));
+
         let report = &data.reports[0];
         assert_eq!(report.oaci, "CWAO");
         assert_eq!(report.name, "MONTREAL");
         assert_eq!(report.messages.len(), 1);
 
-        assert_eq!(report.messages[0].category, "VAA");
+        assert_eq!(report.messages[0].category, MessageCategory::Vaa);
         assert!(report.messages[0].reception_date.is_none());
         assert!(report.messages[0].text.is_none());
 
@@ -65,7 +149,7 @@ mod tests {
         assert_eq!(report2.name, "DARWIN");
         assert_eq!(report2.messages.len(), 5);
 
-        assert_eq!(report2.messages[0].category, "VAA");
+        assert_eq!(report2.messages[0].category, MessageCategory::Vaa);
         assert_eq!(
             report2.messages[0].reception_date,
             Some(String::from("20240708191000"))
@@ -1,13 +1,32 @@
 use crate::center::Center;
-use serde::Deserialize;
+use crate::error::Error;
+use crate::helpers::{ord_by_code, serde_from_str, write_document, Stale};
+use crate::icao::Icao;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RequestOptions {
     /// List of OACI codes of producing centers
     pub airports: Vec<AirportOption>,
+
+    /// Other ICAO location indicators of producing centers
+    pub locations: Vec<Icao>,
 }
 
-#[derive(Debug, strum::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::Display,
+    strum::EnumCount,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::IntoStaticStr,
+)]
+#[strum(ascii_case_insensitive)]
 pub enum AirportOption {
     /// Anchorage
     PAWU,
@@ -25,10 +44,45 @@ pub enum AirportOption {
     KNES,
 }
 
-#[derive(Debug, Deserialize)]
+serde_from_str!(AirportOption);
+ord_by_code!(AirportOption);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vag {
-    #[serde(default, rename = "VAG")]
+    #[serde(default, alias = "VAG")]
     pub reports: Vec<Center>,
+
+    /// `true` if the response was served from the disk cache because the API could not be reached
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+impl Stale for Vag {
+    fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+}
+
+impl Vag {
+    /// Serializes the response back to the XML format of Meteo France's server
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the XML cannot be written.
+    ///
+    pub fn to_xml(&self) -> Result<String, Error> {
+        write_document(|writer| {
+            writer
+                .create_element("groupe")
+                .write_inner_content(|writer| {
+                    self.reports
+                        .iter()
+                        .try_for_each(|report| report.write_xml(writer, "VAG"))
+                })?;
+
+            Ok(())
+        })
+    }
 }
 
 #[cfg(test)]
@@ -45,6 +99,12 @@ mod tests {
 
         let data: Vag = res.unwrap();
 
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<Vag>(&json).unwrap(), data);
+
+        let xml = data.to_xml().unwrap();
+        assert_eq!(parse::<Vag>(&xml).unwrap(), data);
+
         assert_eq!(data.reports.len(), 3);
 
         let report = &data.reports[0];
@@ -0,0 +1,367 @@
+use crate::error::Error;
+use crate::helpers::format_day;
+use crate::product::Product;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Number of days the usage counters are kept, today included
+const RETENTION_DAYS: u64 = 31;
+
+/// Token bucket limiting the number of requests sent to the API by a `Client`, all products
+/// combined.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    /// Maximum number of requests sent in a row before having to wait
+    pub burst: u32,
+
+    /// Time needed to regain the right to send one request, up to `burst` requests
+    pub interval: Duration,
+
+    /// Maximum number of requests per UTC day, every request sent to the API being charged,
+    /// whether it succeeded or not.
+    /// Default is `None` (unlimited).
+    pub daily_budget: Option<u32>,
+
+    /// If `true`, requests fail with `Error::QuotaExceeded` once the daily budget is exhausted,
+    /// otherwise they wait for the next UTC day.
+    /// Default is `false`.
+    pub fail_when_exhausted: bool,
+}
+
+impl RateLimit {
+    #[must_use]
+    pub fn new(burst: u32, interval: Duration) -> RateLimit {
+        RateLimit {
+            burst,
+            interval,
+            daily_budget: None,
+            fail_when_exhausted: false,
+        }
+    }
+}
+
+/// Number of requests sent to the API, per product and per UTC day, over the last 31 days.
+/// Requests which reached the server are counted even if they failed, e.g. with an HTTP error or
+/// an invalid API key, the successful ones being also counted on their own.
+#[derive(Debug, Clone, Default)]
+pub struct Usage {
+    requests: HashMap<(u64, Product), u32>,
+    successes: HashMap<(u64, Product), u32>,
+}
+
+impl Usage {
+    /// Returns the number of requests sent for a product on a day
+    /// e.g. `usage.requests(Product::Opmet, "20240715")`
+    #[must_use]
+    pub fn requests(&self, product: Product, date: &str) -> u32 {
+        self.requests
+            .iter()
+            .filter(|((day, p), _)| *p == product && format_day(*day) == date)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Returns the number of requests sent for a product today
+    #[must_use]
+    pub fn today(&self, product: Product) -> u32 {
+        self.requests
+            .get(&(today(), product))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of requests sent today, all products combined
+    #[must_use]
+    pub fn total_today(&self) -> u32 {
+        self.total(today())
+    }
+
+    /// Returns the number of successful requests sent for a product today
+    #[must_use]
+    pub fn successes_today(&self, product: Product) -> u32 {
+        self.successes
+            .get(&(today(), product))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns every counter as `(date, product, requests)`, sorted by date then product
+    /// e.g. `("20240715", Product::Opmet, 12)`
+    #[must_use]
+    pub fn entries(&self) -> Vec<(String, Product, u32)> {
+        let mut entries = self
+            .requests
+            .iter()
+            .map(|(&(day, product), &count)| (day, product, count))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|&(day, product, _)| (day, product.to_string()));
+
+        entries
+            .into_iter()
+            .map(|(day, product, count)| (format_day(day), product, count))
+            .collect()
+    }
+
+    fn total(&self, day: u64) -> u32 {
+        self.requests
+            .iter()
+            .filter(|((d, _), _)| *d == day)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// Rate limiting and accounting state shared by the requests of a `Client`
+#[derive(Debug, Default)]
+pub(crate) struct Quota {
+    bucket: Mutex<Option<Bucket>>,
+    counters: Mutex<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    usage: Usage,
+
+    /// Requests sent but not done yet, counted against the daily budget until they are
+    pending: u32,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Right to send a request, returned by `Quota::acquire`. The request is only counted if `sent`
+/// is called, dropping the permit gives its share of the daily budget back.
+#[derive(Debug)]
+pub(crate) struct Permit<'a> {
+    quota: &'a Quota,
+    product: Product,
+}
+
+impl Permit<'_> {
+    /// Counts the request sent to the API in the usage, and against the daily budget
+    pub(crate) fn sent(self, succeeded: bool) {
+        let mut counters = self.quota.counters();
+        let usage = &mut counters.usage;
+        let day = today();
+
+        *usage.requests.entry((day, self.product)).or_default() += 1;
+        if succeeded {
+            *usage.successes.entry((day, self.product)).or_default() += 1;
+        }
+
+        usage.requests.retain(|&(d, _), _| d + RETENTION_DAYS > day);
+        usage
+            .successes
+            .retain(|&(d, _), _| d + RETENTION_DAYS > day);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut counters = self.quota.counters();
+        counters.pending = counters.pending.saturating_sub(1);
+    }
+}
+
+impl Quota {
+    /// Waits until a request can be sent for `product`, within the daily budget and the rate
+    /// limit
+    pub(crate) async fn acquire(
+        &self,
+        limit: Option<&RateLimit>,
+        product: Product,
+    ) -> Result<Permit<'_>, Error> {
+        let permit = self.reserve_budget(limit, product).await?;
+
+        if let Some(limit) = limit {
+            self.take_token(limit).await;
+        }
+
+        Ok(permit)
+    }
+
+    pub(crate) fn usage(&self) -> Usage {
+        self.counters().usage.clone()
+    }
+
+    fn counters(&self) -> std::sync::MutexGuard<'_, Counters> {
+        self.counters.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Reserves a share of the daily budget for the request until it is done, waiting for the
+    /// next day if it is exhausted
+    async fn reserve_budget(
+        &self,
+        limit: Option<&RateLimit>,
+        product: Product,
+    ) -> Result<Permit<'_>, Error> {
+        let budget = limit.and_then(|limit| limit.daily_budget);
+
+        loop {
+            {
+                let mut counters = self.counters();
+
+                if budget
+                    .is_none_or(|budget| counters.usage.total(today()) + counters.pending < budget)
+                {
+                    counters.pending += 1;
+
+                    return Ok(Permit {
+                        quota: self,
+                        product,
+                    });
+                }
+            }
+
+            if limit.is_some_and(|limit| limit.fail_when_exhausted) {
+                return Err(Error::QuotaExceeded(product));
+            }
+
+            let now = seconds_since_epoch();
+            let next_day = SECONDS_PER_DAY - now % SECONDS_PER_DAY;
+            tokio::time::sleep(Duration::from_secs(next_day)).await;
+        }
+    }
+
+    /// Takes a token from the bucket, waiting for one to be available
+    async fn take_token(&self, limit: &RateLimit) {
+        let burst = f64::from(limit.burst.max(1));
+
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+                let bucket = bucket.get_or_insert_with(|| Bucket {
+                    tokens: burst,
+                    refilled_at: Instant::now(),
+                });
+
+                if !limit.interval.is_zero() {
+                    let refilled =
+                        bucket.refilled_at.elapsed().as_secs_f64() / limit.interval.as_secs_f64();
+                    bucket.tokens = (bucket.tokens + refilled).min(burst);
+                }
+                bucket.refilled_at = Instant::now();
+
+                if limit.interval.is_zero() || bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+
+                limit.interval.mul_f64(1.0 - bucket.tokens)
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+fn seconds_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Returns the current UTC day, as a number of days since the UNIX epoch
+fn today() -> u64 {
+    seconds_since_epoch() / SECONDS_PER_DAY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_day() {
+        assert_eq!(format_day(0), "19700101");
+        assert_eq!(format_day(11016), "20000229");
+        assert_eq!(format_day(19999), "20241003");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let quota = Quota::default();
+        let limit = RateLimit::new(2, Duration::from_millis(50));
+
+        let start = Instant::now();
+        for _ in 0..4 {
+            quota
+                .acquire(Some(&limit), Product::Opmet)
+                .await
+                .unwrap()
+                .sent(true);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        quota.acquire(None, Product::Maa).await.unwrap().sent(true);
+
+        // A request which reached the server is counted even if it failed, unlike a request
+        // which could not be sent
+        quota.acquire(None, Product::Maa).await.unwrap().sent(false);
+        drop(quota.acquire(None, Product::Sigmet).await.unwrap());
+
+        let usage = quota.usage();
+        assert_eq!(usage.today(Product::Opmet), 4);
+        assert_eq!(usage.successes_today(Product::Opmet), 4);
+        assert_eq!(usage.today(Product::Maa), 2);
+        assert_eq!(usage.successes_today(Product::Maa), 1);
+        assert_eq!(usage.today(Product::Sigmet), 0);
+        assert_eq!(usage.total_today(), 6);
+        assert_eq!(
+            usage.requests(Product::Opmet, &format_day(today())),
+            usage.today(Product::Opmet)
+        );
+        assert_eq!(usage.entries().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_daily_budget() {
+        let quota = Quota::default();
+        let mut limit = RateLimit::new(10, Duration::ZERO);
+        limit.daily_budget = Some(2);
+        limit.fail_when_exhausted = true;
+
+        // The requests being sent count against the budget until they are done
+        let permit = quota.acquire(Some(&limit), Product::Opmet).await.unwrap();
+        quota
+            .acquire(Some(&limit), Product::Sigmet)
+            .await
+            .unwrap()
+            .sent(true);
+        assert!(matches!(
+            quota.acquire(Some(&limit), Product::Opmet).await,
+            Err(Error::QuotaExceeded(Product::Opmet))
+        ));
+
+        // Once failed, its share of the budget is given back
+        drop(permit);
+        assert_eq!(quota.usage().total_today(), 1);
+        quota
+            .acquire(Some(&limit), Product::Opmet)
+            .await
+            .unwrap()
+            .sent(true);
+        assert_eq!(quota.usage().total_today(), 2);
+        assert!(quota.acquire(Some(&limit), Product::Opmet).await.is_err());
+
+        // Old days are expired
+        let old = today() - RETENTION_DAYS;
+        quota
+            .counters()
+            .usage
+            .requests
+            .insert((old, Product::Opmet), 1);
+        quota.acquire(None, Product::Maa).await.unwrap().sent(true);
+        assert!(quota
+            .usage()
+            .entries()
+            .iter()
+            .all(|(date, _, _)| *date != format_day(old)));
+    }
+}
@@ -0,0 +1,520 @@
+use crate::airport::Airport;
+use crate::error::Error;
+use crate::helpers::format_day;
+use crate::icao::Icao;
+use crate::message::Message;
+use crate::message_category::MessageCategory;
+use crate::product::Product;
+use crate::report::{Report, Response};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use strum::IntoEnumIterator;
+
+/// Reception dates are unknown for some products (e.g. OPMET), they are stored as an empty string
+/// so that the unique constraint applies to them, as are the missing texts
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id INTEGER PRIMARY KEY,
+        product TEXT NOT NULL,
+        location TEXT NOT NULL,
+        name TEXT NOT NULL,
+        category TEXT NOT NULL,
+        reception_date TEXT NOT NULL,
+        text TEXT NOT NULL,
+        archived_at INTEGER NOT NULL,
+        UNIQUE (location, category, reception_date, text)
+    );
+    CREATE INDEX IF NOT EXISTS messages_location ON messages (location, archived_at);
+    CREATE INDEX IF NOT EXISTS messages_archived_at ON messages (archived_at);
+";
+
+/// Responses whose messages can be archived, along with the product they come from
+pub trait Archived: Response {
+    const PRODUCT: Product;
+}
+
+impl Archived for crate::opmet::Opmet {
+    const PRODUCT: Product = Product::Opmet;
+}
+
+impl Archived for crate::sigmet::Sigmet {
+    const PRODUCT: Product = Product::Sigmet;
+}
+
+impl Archived for crate::maa::Maa {
+    const PRODUCT: Product = Product::Maa;
+}
+
+impl Archived for crate::predec::Predec {
+    const PRODUCT: Product = Product::Predec;
+}
+
+impl Archived for crate::vaa::Vaa {
+    const PRODUCT: Product = Product::Vaa;
+}
+
+impl Archived for crate::tca::Tca {
+    const PRODUCT: Product = Product::Tca;
+}
+
+impl Archived for crate::sw::SpaceWeather {
+    const PRODUCT: Product = Product::SpaceWeather;
+}
+
+/// `SQLite` archive of the messages retrieved from the API, to keep their history beyond what the
+/// server sends (the latest reports, 48 hours for MAA).
+///
+/// A message is stored once per location, whatever the number of responses it was part of: two
+/// messages are the same if their location, category, reception date and text are. Each message
+/// is dated by the time it was first archived, its reception date being unknown for some products.
+#[derive(Debug)]
+pub struct Archive {
+    /// How long the messages are kept, applied every time a response is stored.
+    /// Default is to keep them forever.
+    pub retention: Retention,
+
+    connection: Mutex<Connection>,
+}
+
+/// How long the messages are kept in an `Archive`
+#[derive(Debug, Clone, Default)]
+pub struct Retention {
+    /// Age after which the messages are deleted, unless their product has its own retention.
+    /// Default is `None` (forever).
+    pub max_age: Option<Duration>,
+
+    /// Retention of some products, overriding `max_age`
+    /// e.g. `(Product::Opmet, Duration::from_secs(7 * 86_400))`
+    pub products: Vec<(Product, Duration)>,
+}
+
+/// Filters of the messages read from an `Archive`, all of them are returned by default
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// List of OACI codes of the airports
+    /// e.g. `Airport::LFBO`, `Airport::LFBA`
+    pub airports: Vec<Airport>,
+
+    /// Other ICAO location indicators, e.g. `LFMM`, `KWNP`
+    pub locations: Vec<Icao>,
+
+    /// e.g. `Product::Opmet`, `Product::Maa`
+    pub products: Vec<Product>,
+
+    /// e.g. `MessageCategory::Metar`, `MessageCategory::Speci`
+    pub categories: Vec<MessageCategory>,
+
+    /// Messages received at or after this time, or archived at or after it for the messages
+    /// without reception date
+    pub from: Option<SystemTime>,
+
+    /// Messages received before this time, or archived before it for the messages without
+    /// reception date
+    pub to: Option<SystemTime>,
+
+    /// Maximum number of messages, the most recent ones being returned first
+    pub limit: Option<usize>,
+}
+
+/// Message read from an `Archive`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Product the message comes from
+    pub product: Product,
+
+    /// e.g. LFBO, LFMM
+    pub location: String,
+
+    /// e.g. TOULOUSE BLAGNAC, MARSEILLE
+    pub name: String,
+
+    pub message: Message,
+
+    /// When the message was first archived
+    pub archived_at: SystemTime,
+}
+
+impl Archive {
+    /// Opens the archive stored in a `SQLite` database, created if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or its schema cannot be created.
+    ///
+    pub fn open(path: impl AsRef<Path>) -> Result<Archive, Error> {
+        Archive::with_connection(Connection::open(path)?)
+    }
+
+    /// Opens an archive held in memory, lost once dropped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be created.
+    ///
+    pub fn in_memory() -> Result<Archive, Error> {
+        Archive::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Archive, Error> {
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Archive {
+            retention: Retention::default(),
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Stores the messages of a response, then deletes the ones older than the retention.
+    /// Returns the number of messages which were not archived yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the messages cannot be written.
+    ///
+    pub fn store<R: Archived>(&self, response: &R) -> Result<usize, Error> {
+        self.store_at(response, SystemTime::now())
+    }
+
+    /// Stores the messages of a response retrieved at another time, e.g. an `Entry` of the
+    /// `DiskCache` along with its `fetched_at`, then deletes the ones older than the retention.
+    /// Returns the number of messages which were not archived yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the messages cannot be written.
+    ///
+    pub fn store_at<R: Archived>(
+        &self,
+        response: &R,
+        archived_at: SystemTime,
+    ) -> Result<usize, Error> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let mut stored = 0;
+
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT OR IGNORE INTO messages
+                    (product, location, name, category, reception_date, text, archived_at)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+
+            for report in response.reports() {
+                for message in report.messages() {
+                    stored += statement.execute(params![
+                        R::PRODUCT.to_string(),
+                        report.oaci(),
+                        report.name(),
+                        message.category.to_string(),
+                        message.reception_date.unwrap_or_default(),
+                        message.text.unwrap_or_default(),
+                        seconds(archived_at),
+                    ])?;
+                }
+            }
+        }
+
+        transaction.commit()?;
+        drop(connection);
+        self.purge()?;
+
+        Ok(stored)
+    }
+
+    /// Returns the archived messages matching a query, the most recent ones first
+    /// e.g. the METARs of LFBO over the last day
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the messages cannot be read.
+    ///
+    pub fn query(&self, query: &Query) -> Result<Vec<Record>, Error> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::<Value>::new();
+
+        let locations = query
+            .airports
+            .iter()
+            .map(ToString::to_string)
+            .chain(query.locations.iter().map(ToString::to_string))
+            .collect::<Vec<_>>();
+        let products = query
+            .products
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let categories = query
+            .categories
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        for (column, list) in [
+            ("location", locations),
+            ("product", products),
+            ("category", categories),
+        ] {
+            if !list.is_empty() {
+                conditions.push(format!(
+                    "{column} IN ({})",
+                    vec!["?"; list.len()].join(", ")
+                ));
+                values.extend(list.into_iter().map(Value::Text));
+            }
+        }
+
+        for (bound, operator) in [(query.from, ">="), (query.to, "<")] {
+            if let Some(time) = bound {
+                conditions.push(format!(
+                    "CASE WHEN reception_date = '' THEN archived_at {operator} ? \
+                        ELSE reception_date {operator} ? END"
+                ));
+                values.push(Value::Integer(seconds(time)));
+                values.push(Value::Text(reception_date(time)));
+            }
+        }
+
+        let mut sql = String::from(
+            "SELECT product, location, name, category, reception_date, text, archived_at
+                FROM messages",
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY archived_at DESC, id DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(" LIMIT ");
+            sql.push_str(&limit.to_string());
+        }
+
+        let connection = self.connection();
+        let mut statement = connection.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| {
+            let product = row.get::<_, String>(0)?;
+            let reception_date = row.get::<_, String>(4)?;
+            let text = row.get::<_, String>(5)?;
+            let archived_at = row.get::<_, i64>(6)?;
+
+            Ok(Record {
+                product: Product::iter()
+                    .find(|p| p.to_string() == product)
+                    .ok_or_else(|| {
+                        rusqlite::Error::InvalidColumnType(
+                            0,
+                            String::from("product"),
+                            rusqlite::types::Type::Text,
+                        )
+                    })?,
+                location: row.get(1)?,
+                name: row.get(2)?,
+                message: Message {
+                    category: MessageCategory::from(row.get::<_, String>(3)?),
+                    reception_date: (!reception_date.is_empty()).then_some(reception_date),
+                    text: (!text.is_empty()).then_some(text),
+                },
+                archived_at: UNIX_EPOCH
+                    + Duration::from_secs(u64::try_from(archived_at).unwrap_or_default()),
+            })
+        })?;
+
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Deletes the messages older than the retention, returns the number of deleted messages
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the messages cannot be deleted.
+    ///
+    pub fn purge(&self) -> Result<usize, Error> {
+        let connection = self.connection();
+        let now = SystemTime::now();
+        let mut deleted = 0;
+
+        for (product, max_age) in &self.retention.products {
+            deleted += connection.execute(
+                "DELETE FROM messages WHERE product = ?1 AND archived_at < ?2",
+                params![product.to_string(), seconds(before(now, *max_age))],
+            )?;
+        }
+
+        if let Some(max_age) = self.retention.max_age {
+            let products = self
+                .retention
+                .products
+                .iter()
+                .map(|(product, _)| Value::Text(product.to_string()))
+                .collect::<Vec<_>>();
+
+            let sql = format!(
+                "DELETE FROM messages WHERE archived_at < ? AND product NOT IN ({})",
+                vec!["?"; products.len()].join(", ")
+            );
+
+            deleted += connection.execute(
+                &sql,
+                params_from_iter(
+                    std::iter::once(Value::Integer(seconds(before(now, max_age)))).chain(products),
+                ),
+            )?;
+        }
+
+        Ok(deleted)
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the number of seconds since the UNIX epoch, 0 for the times before it
+fn seconds(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |duration| {
+        i64::try_from(duration.as_secs()).unwrap_or(i64::MAX)
+    })
+}
+
+/// Formats a time as a reception date of the server, in UTC, e.g. `20240715104200`
+fn reception_date(time: SystemTime) -> String {
+    let seconds = u64::try_from(seconds(time)).unwrap_or_default();
+    let (day, time) = (seconds / 86_400, seconds % 86_400);
+
+    format!(
+        "{}{:02}{:02}{:02}",
+        format_day(day),
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Returns the time some duration before another, the UNIX epoch if it would be earlier
+fn before(time: SystemTime, duration: Duration) -> SystemTime {
+    time.checked_sub(duration).unwrap_or(UNIX_EPOCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    #[test]
+    fn test_archive() {
+        let directory =
+            std::env::temp_dir().join(format!("aeroweb-archive-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("archive.sqlite");
+
+        let data = std::fs::read_to_string("./data/opmet2.xml").unwrap();
+        let opmet: crate::opmet::Opmet = crate::parse(&data).unwrap();
+        let data = std::fs::read_to_string("./data/maa.xml").unwrap();
+        let maa: crate::maa::Maa = crate::parse(&data).unwrap();
+
+        let now = SystemTime::now();
+        let archive = Archive::open(&path).unwrap();
+        // LFBO and LFBA: METAR, TAF, SIGMET
+        assert_eq!(archive.store_at(&opmet, before(now, 10 * DAY)).unwrap(), 6);
+        assert_eq!(archive.store(&opmet).unwrap(), 0);
+        assert_eq!(archive.store(&maa).unwrap(), 6);
+        drop(archive);
+
+        let mut archive = Archive::open(&path).unwrap();
+        let records = archive.query(&Query::default()).unwrap();
+        assert_eq!(records.len(), 12);
+        assert_eq!(records[0].product, Product::Maa);
+        assert!(records[0].message.reception_date.is_some());
+
+        let records = archive
+            .query(&Query {
+                airports: vec![Airport::LFBO],
+                products: vec![Product::Opmet],
+                categories: vec![MessageCategory::Metar],
+                from: Some(before(now, 11 * DAY)),
+                to: Some(before(now, 9 * DAY)),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].location, "LFBO");
+        assert_eq!(records[0].name, "TOULOUSE BLAGNAC");
+        assert_eq!(records[0].message.reception_date, None);
+        assert!(records[0]
+            .message
+            .text
+            .as_deref()
+            .unwrap()
+            .starts_with("METAR LFBO"));
+
+        // The MAAs are filtered on their reception date rather than the time they were archived
+        let july_15 = UNIX_EPOCH + Duration::from_secs(1_721_001_600);
+        assert_eq!(
+            reception_date(july_15 + Duration::from_secs(38_520)),
+            "20240715104200"
+        );
+        let records = archive
+            .query(&Query {
+                locations: vec!["LFPG".parse().unwrap()],
+                from: Some(july_15 + Duration::from_secs(10 * 3600)),
+                to: Some(july_15 + Duration::from_secs(11 * 3600)),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(archive
+            .query(&Query {
+                products: vec![Product::Maa],
+                from: Some(before(now, DAY)),
+                ..Default::default()
+            })
+            .unwrap()
+            .is_empty());
+        let records = archive
+            .query(&Query {
+                locations: vec!["LFPG".parse().unwrap()],
+                limit: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 2);
+
+        // A message without text is read back without text
+        archive
+            .connection()
+            .execute(
+                "INSERT INTO messages
+                    (product, location, name, category, reception_date, text, archived_at)
+                    VALUES ('MAA', 'LFBO', 'TOULOUSE BLAGNAC', 'MAA', '', '', ?1)",
+                params![seconds(now)],
+            )
+            .unwrap();
+        let records = archive
+            .query(&Query {
+                airports: vec![Airport::LFBO],
+                products: vec![Product::Maa],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message.text, None);
+        archive
+            .connection()
+            .execute("DELETE FROM messages WHERE text = ''", [])
+            .unwrap();
+
+        archive.retention = Retention {
+            max_age: Some(30 * DAY),
+            products: vec![(Product::Opmet, 7 * DAY)],
+        };
+        assert_eq!(archive.purge().unwrap(), 6);
+        assert_eq!(archive.query(&Query::default()).unwrap().len(), 6);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use crate::error::Error;
+use crate::product::Product;
+use futures::future::{BoxFuture, FutureExt};
+use std::collections::HashMap;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+/// Sends the requests of a `Client` and returns the raw body of the responses.
+///
+/// Without a transport, a `Client` sends its requests with its own `http_client`. `HttpTransport`
+/// does the same as a transport, e.g. to be wrapped by a `RecordingTransport`, and
+/// `MemoryTransport` serves fixtures for tests.
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    /// Retrieves the raw body of the response for a product and its parameters,
+    /// e.g. `Product::Opmet` and `LIEUID=LFBO|LFBA`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the response cannot be retrieved.
+    ///
+    fn fetch<'a>(
+        &'a self,
+        product: Product,
+        params: &'a str,
+    ) -> BoxFuture<'a, Result<String, Error>>;
+}
+
+/// URL of Meteo France's server
+pub(crate) const API_BASE_URL: &str = "https://aviation.meteo.fr/FR/aviation/serveur_donnees.jsp";
+
+/// Retrieves the data from Meteo France's server with `reqwest`. Responses with a non-2xx status
+/// fail with `Error::Fetch`, so that the disk cache can serve the last known response instead.
+#[derive(Debug)]
+pub struct HttpTransport {
+    pub http_client: reqwest::Client,
+    pub api_base_url: String,
+    pub api_key: String,
+}
+
+impl HttpTransport {
+    #[must_use]
+    pub fn new(api_key: &str) -> HttpTransport {
+        HttpTransport {
+            http_client: reqwest::Client::new(),
+            api_base_url: String::from(API_BASE_URL),
+            api_key: String::from(api_key),
+        }
+    }
+}
+
+impl Transport for HttpTransport {
+    fn fetch<'a>(
+        &'a self,
+        product: Product,
+        params: &'a str,
+    ) -> BoxFuture<'a, Result<String, Error>> {
+        http_fetch(
+            &self.http_client,
+            &self.api_base_url,
+            &self.api_key,
+            product,
+            params,
+        )
+        .boxed()
+    }
+}
+
+/// Sends a request to the server at `api_base_url` and returns the raw body of the response
+pub(crate) async fn http_fetch(
+    http_client: &reqwest::Client,
+    api_base_url: &str,
+    api_key: &str,
+    product: Product,
+    params: &str,
+) -> Result<String, Error> {
+    Ok(http_client
+        .get(format!(
+            "{api_base_url}?ID={api_key}&TYPE_DONNEES={product}&{params}"
+        ))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?)
+}
+
+/// Serves in-memory responses, whatever the parameters of the request
+#[derive(Debug, Default)]
+pub struct MemoryTransport {
+    pub responses: HashMap<Product, String>,
+}
+
+impl MemoryTransport {
+    #[must_use]
+    pub fn new() -> MemoryTransport {
+        MemoryTransport::default()
+    }
+
+    /// Loads the fixtures of a directory, named like the ones of this repository: `maps.xml`,
+    /// `flight_plan.xml`, and the `TYPE_DONNEES` of the other products, e.g. `opmet2.xml`.
+    /// Products without a fixture are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a fixture exists but cannot be read.
+    ///
+    pub fn from_dir(directory: impl AsRef<Path>) -> Result<MemoryTransport, Error> {
+        let mut transport = MemoryTransport::new();
+
+        for product in Product::iter() {
+            let name = match product {
+                Product::FlightPlan => String::from("flight_plan"),
+                Product::Maps => String::from("maps"),
+                _ => product.to_string().to_lowercase(),
+            };
+            let path = directory.as_ref().join(format!("{name}.xml"));
+
+            match std::fs::read_to_string(path) {
+                Ok(body) => transport.insert(product, body),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(transport)
+    }
+
+    /// Sets the response served for a product
+    pub fn insert(&mut self, product: Product, body: impl Into<String>) {
+        self.responses.insert(product, body.into());
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn fetch<'a>(
+        &'a self,
+        product: Product,
+        _params: &'a str,
+    ) -> BoxFuture<'a, Result<String, Error>> {
+        let res = self
+            .responses
+            .get(&product)
+            .cloned()
+            .ok_or_else(|| Error::Transport(format!("No response for {product}").into()));
+
+        futures::future::ready(res).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_transport() {
+        let transport = MemoryTransport::from_dir("./data").unwrap();
+
+        assert_eq!(transport.responses.len(), Product::iter().count());
+
+        let res = transport.fetch(Product::Opmet, "LIEUID=LFBO").await;
+        assert_eq!(
+            res.unwrap(),
+            std::fs::read_to_string("./data/opmet2.xml").unwrap()
+        );
+
+        let transport = MemoryTransport::new();
+        assert!(matches!(
+            transport.fetch(Product::Opmet, "LIEUID=LFBO").await,
+            Err(Error::Transport(_))
+        ));
+    }
+}
@@ -1,4 +1,20 @@
-#[derive(Debug, strum::Display)]
+use crate::airport_info::AirportInfo;
+use crate::helpers::{ord_by_code, serde_from_str};
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::Display,
+    strum::EnumCount,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::IntoStaticStr,
+)]
+#[strum(ascii_case_insensitive)]
 /// List of French Airports
 /// Source: <https://en.wikipedia.org/wiki/List_of_airports_in_France>
 ///
@@ -934,3 +950,42 @@ pub enum Airport {
     /// Pontoise – Cormeilles
     LFPT,
 }
+
+serde_from_str!(Airport);
+ord_by_code!(Airport);
+
+impl Airport {
+    /// Returns the metadata of the airport from the bundled dataset
+    #[must_use]
+    pub fn info(self) -> &'static AirportInfo {
+        &AirportInfo::all()[&self]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::{EnumCount, IntoEnumIterator};
+
+    #[test]
+    fn test_airport() {
+        assert_eq!("LFBO".parse::<Airport>().unwrap(), Airport::LFBO);
+        assert_eq!("lfbo".parse::<Airport>().unwrap(), Airport::LFBO);
+        assert_eq!(Airport::try_from("LfBa").unwrap(), Airport::LFBA);
+        assert!("EGLL".parse::<Airport>().is_err());
+
+        assert_eq!(Airport::iter().count(), Airport::COUNT);
+        assert_eq!(Airport::iter().next(), Some(Airport::LFXA));
+        assert!(Airport::LFBA < Airport::LFBO);
+        assert!(Airport::LFXA > Airport::LFBO);
+        let mut airports = vec![Airport::LFXA, Airport::LFBO, Airport::LFBA];
+        airports.sort();
+        assert_eq!(airports, [Airport::LFBA, Airport::LFBO, Airport::LFXA]);
+
+        assert_eq!(serde_json::to_string(&Airport::LFBO).unwrap(), "\"LFBO\"");
+        assert_eq!(
+            serde_json::from_str::<Vec<Airport>>("[\"LFBO\", \"lfba\"]").unwrap(),
+            [Airport::LFBO, Airport::LFBA]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use crate::airport::Airport;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Bundled dataset of the airports, one line per `Airport`.
+///
+/// Names, departments and regions follow the list of airports in France the `Airport` enum is
+/// extracted from, airfields named after an air base (BA) or a military quarter are military.
+/// All of them are in metropolitan France, hence in the Europe/Paris time zone.
+///
+/// Positions and elevations are bundled separately, see `aeroweb::geodata::Geodata`.
+const DATASET: &str = include_str!("airports.csv");
+
+static AIRPORTS: OnceLock<HashMap<Airport, AirportInfo>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirportInfo {
+    pub airport: Airport,
+
+    /// e.g. Toulouse–Blagnac, Agen La Garenne
+    pub name: &'static str,
+
+    /// Number and name of the department
+    /// e.g. 31 Haute-Garonne, 2A Corse-du-Sud
+    pub department: &'static str,
+
+    /// e.g. Occitanie, Île-de-France
+    pub region: &'static str,
+
+    /// `true` for air bases and other military airfields
+    pub military: bool,
+
+    /// IANA time zone
+    /// e.g. Europe/Paris
+    pub timezone: &'static str,
+}
+
+impl AirportInfo {
+    /// Parses a line of the dataset, e.g.
+    /// `LFBO;Toulouse–Blagnac;31 Haute-Garonne;Occitanie;false;Europe/Paris`
+    fn parse(line: &'static str) -> Option<AirportInfo> {
+        let mut fields = line.split(';');
+        let mut next = || fields.next();
+
+        Some(AirportInfo {
+            airport: next()?.parse().ok()?,
+            name: next()?,
+            department: next()?,
+            region: next()?,
+            military: next()?.parse().ok()?,
+            timezone: next()?,
+        })
+    }
+
+    /// Returns the airports of the dataset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a line of the bundled dataset is malformed, which is checked by the tests.
+    ///
+    pub(crate) fn all() -> &'static HashMap<Airport, AirportInfo> {
+        AIRPORTS.get_or_init(|| {
+            DATASET
+                .lines()
+                .skip(1)
+                .map(|line| {
+                    let info = AirportInfo::parse(line)
+                        .unwrap_or_else(|| panic!("Invalid airport in the dataset: {line}"));
+
+                    (info.airport, info)
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::{EnumCount, IntoEnumIterator};
+
+    #[test]
+    fn test_airport_info() {
+        assert_eq!(AirportInfo::all().len(), Airport::COUNT);
+
+        // The dataset follows the order of the enum
+        let codes = DATASET
+            .lines()
+            .skip(1)
+            .map(|line| line.split(';').next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            Airport::iter()
+                .map(|airport| airport.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        let info = Airport::LFBO.info();
+        assert_eq!(info.name, "Toulouse–Blagnac");
+        assert_eq!(info.department, "31 Haute-Garonne");
+        assert_eq!(info.region, "Occitanie");
+        assert!(!info.military);
+        assert_eq!(info.timezone, "Europe/Paris");
+
+        let info = Airport::LFMI.info();
+        assert!(info.military);
+        assert_eq!(info.department, "13 Bouches-du-Rhône");
+
+        // Overseas departments (971 to 976) would be in other time zones
+        assert!(AirportInfo::all().values().all(|info| {
+            let (code, _) = info.department.split_once(' ').unwrap();
+
+            code.len() == 2 && info.timezone == "Europe/Paris"
+        }));
+    }
+}
@@ -1,21 +1,40 @@
-use crate::helpers::{de_option_link, de_option_string};
-use serde::Deserialize;
+use crate::helpers::{de_option_link, de_option_string, link_path, write_cdata, XmlWriter};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Center {
     /// e.g. FMEE, RJTD
-    #[serde(rename = "@oaci")]
+    #[serde(alias = "@oaci")]
     pub oaci: String,
 
     /// e.g. LA REUNION, TOKYO
-    #[serde(rename = "@nom")]
+    #[serde(alias = "@nom")]
     pub name: String,
 
     /// e.g. 20240620210000
-    #[serde(rename = "@date_reception", deserialize_with = "de_option_string")]
+    #[serde(alias = "@date_reception", deserialize_with = "de_option_string")]
     pub reception_date: Option<String>,
 
     /// e.g. <https://aviation.meteo.fr/...>
     #[serde(default, deserialize_with = "de_option_link", alias = "lien")]
     pub link: Option<String>,
 }
+
+impl Center {
+    /// Writes the center as a `name` element, e.g. VAG, TCAG
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter, name: &str) -> std::io::Result<()> {
+        writer
+            .create_element(name)
+            .with_attribute((
+                "date_reception",
+                self.reception_date.as_deref().unwrap_or("NIL"),
+            ))
+            .with_attribute(("oaci", self.oaci.as_str()))
+            .with_attribute(("nom", self.name.as_str()))
+            .write_inner_content(|writer| {
+                write_cdata(writer, "lien", Some(link_path(self.link.as_deref())), "NIL")
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use serde::{Deserialize, Serialize};
+
+/// Mean radius of the Earth in nautical miles
+const EARTH_RADIUS: f64 = 3440.065;
+
+/// Position in decimal degrees (WGS 84)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Coordinates {
+    /// Positive north of the equator, e.g. 43.6291
+    pub latitude: f64,
+
+    /// Positive east of the Greenwich meridian, e.g. 1.3638
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    #[must_use]
+    pub fn new(latitude: f64, longitude: f64) -> Coordinates {
+        Coordinates {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Returns the great-circle distance in nautical miles, with the haversine formula
+    #[must_use]
+    pub fn distance(&self, other: Coordinates) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.longitude - self.longitude).to_radians();
+
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+
+    /// Returns the initial true bearing in degrees, from 0 (north) to 360, to follow the
+    /// great circle to `other`
+    #[must_use]
+    pub fn bearing(&self, other: Coordinates) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lon = (other.longitude - self.longitude).to_radians();
+
+        let y = d_lon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Returns the position at `fraction` (0 to 1) of the great circle to `other`
+    #[must_use]
+    pub fn intermediate(&self, other: Coordinates, fraction: f64) -> Coordinates {
+        let angle = self.distance(other) / EARTH_RADIUS;
+
+        if angle == 0.0 {
+            return *self;
+        }
+
+        let (lat1, lon1) = (self.latitude.to_radians(), self.longitude.to_radians());
+        let (lat2, lon2) = (other.latitude.to_radians(), other.longitude.to_radians());
+        let a = ((1.0 - fraction) * angle).sin() / angle.sin();
+        let b = (fraction * angle).sin() / angle.sin();
+
+        let x = a * lat1.cos() * lon1.cos() + b * lat2.cos() * lon2.cos();
+        let y = a * lat1.cos() * lon1.sin() + b * lat2.cos() * lon2.sin();
+        let z = a * lat1.sin() + b * lat2.sin();
+
+        Coordinates::new(z.atan2(x.hypot(y)).to_degrees(), y.atan2(x).to_degrees())
+    }
+
+    /// Returns the position reached after `distance` nautical miles on the great circle starting
+    /// with the true `bearing` in degrees
+    #[must_use]
+    pub fn destination(&self, bearing: f64, distance: f64) -> Coordinates {
+        let lat1 = self.latitude.to_radians();
+        let angle = distance / EARTH_RADIUS;
+        let bearing = bearing.to_radians();
+
+        let lat2 = (lat1.sin() * angle.cos() + lat1.cos() * angle.sin() * bearing.cos()).asin();
+        let d_lon =
+            (bearing.sin() * angle.sin() * lat1.cos()).atan2(angle.cos() - lat1.sin() * lat2.sin());
+
+        Coordinates::new(
+            lat2.to_degrees(),
+            (self.longitude + d_lon.to_degrees() + 540.0).rem_euclid(360.0) - 180.0,
+        )
+    }
+}
+
+/// Returns `true` if the position is inside the polygon, with the even-odd rule on the latitudes
+/// and longitudes. The last vertex is implicitly linked to the first one.
+pub(crate) fn polygon_contains(points: &[Coordinates], position: Coordinates) -> bool {
+    let Some(&last) = points.last() else {
+        return false;
+    };
+
+    let mut inside = false;
+    let mut previous = last;
+
+    for &point in points {
+        if (point.latitude > position.latitude) != (previous.latitude > position.latitude) {
+            let longitude = point.longitude
+                + (position.latitude - point.latitude) / (previous.latitude - point.latitude)
+                    * (previous.longitude - point.longitude);
+
+            if position.longitude < longitude {
+                inside = !inside;
+            }
+        }
+
+        previous = point;
+    }
+
+    inside
+}
+
+/// Returns the positions sampled every `step` nautical miles on the great circles between the
+/// points of a route, starting with the first point, along with their distance from it
+pub(crate) fn sample_route(route: &[Coordinates], step: f64) -> Vec<(Coordinates, f64)> {
+    let mut samples = route
+        .first()
+        .map(|&position| vec![(position, 0.0)])
+        .unwrap_or_default();
+    let mut along = 0.0;
+
+    for leg in route.windows(2) {
+        let (from, to) = (leg[0], leg[1]);
+        let length = from.distance(to);
+        // Truncation is fine, the number of steps only sets the sampling precision
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let steps = (length / step).ceil().max(1.0) as usize;
+
+        for i in 1..=steps {
+            #[allow(clippy::cast_precision_loss)]
+            let fraction = i as f64 / steps as f64;
+
+            samples.push((from.intermediate(to, fraction), along + length * fraction));
+        }
+
+        along += length;
+    }
+
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coordinates() {
+        let origin = Coordinates::new(0.0, 0.0);
+
+        assert!((origin.distance(Coordinates::new(0.0, 1.0)) - 60.04).abs() < 0.01);
+        assert!((origin.distance(Coordinates::new(1.0, 0.0)) - 60.04).abs() < 0.01);
+        assert_eq!(origin.distance(origin), 0.0);
+
+        assert!(origin.bearing(Coordinates::new(1.0, 0.0)).abs() < 1e-9);
+        assert!((origin.bearing(Coordinates::new(0.0, 1.0)) - 90.0).abs() < 1e-9);
+        assert!((origin.bearing(Coordinates::new(-1.0, 0.0)) - 180.0).abs() < 1e-9);
+        assert!((origin.bearing(Coordinates::new(0.0, -1.0)) - 270.0).abs() < 1e-9);
+
+        let east = Coordinates::new(0.0, 2.0);
+        let middle = origin.intermediate(east, 0.5);
+        assert!(middle.latitude.abs() < 1e-9 && (middle.longitude - 1.0).abs() < 1e-9);
+        assert_eq!(origin.intermediate(origin, 0.5), origin);
+
+        let north = origin.destination(0.0, 60.04);
+        assert!((north.latitude - 1.0).abs() < 1e-3 && north.longitude.abs() < 1e-9);
+        assert!(
+            (Coordinates::new(0.0, 179.5)
+                .destination(90.0, 60.04)
+                .longitude
+                + 179.5)
+                .abs()
+                < 1e-3
+        );
+
+        let square = [
+            Coordinates::new(-1.0, -1.0),
+            Coordinates::new(1.0, -1.0),
+            Coordinates::new(1.0, 1.0),
+            Coordinates::new(-1.0, 1.0),
+        ];
+        assert!(polygon_contains(&square, origin));
+        assert!(!polygon_contains(&square, east));
+        assert!(!polygon_contains(&[], origin));
+
+        let samples = sample_route(&[origin, east], 50.0);
+        assert_eq!(samples.len(), 4);
+        assert_eq!(samples[0], (origin, 0.0));
+        assert!((samples[3].1 - origin.distance(east)).abs() < 1e-9);
+        assert!(sample_route(&[], 50.0).is_empty());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::message::Message;
+use crate::message_category::MessageCategory;
+use crate::report::{Report, Response};
+use serde::{Deserialize, Serialize};
+
+/// Minimum share of common words for a removed and an added message of the same category to be
+/// considered as a modified message, when the location has several messages of this category
+const SIMILARITY: f64 = 0.5;
+
+/// Changes of the messages between two responses of a product, per location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diff {
+    /// Locations with at least one change, those of the newer response first, in its order
+    pub locations: Vec<LocationDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocationDiff {
+    /// e.g. LFBO, LFMM
+    pub oaci: String,
+
+    /// e.g. TOULOUSE BLAGNAC, MARSEILLE
+    pub name: String,
+
+    pub changes: Vec<MessageDiff>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageDiff {
+    pub kind: DiffKind,
+
+    /// e.g. METAR, MAA
+    pub category: MessageCategory,
+
+    /// Message of the older response, `None` if it was added
+    pub older: Option<Message>,
+
+    /// Message of the newer response, `None` if it was removed
+    pub newer: Option<Message>,
+
+    /// Word by word differences between the texts of the messages
+    pub edits: Vec<TextEdit>,
+}
+
+/// Words kept, inserted or deleted between two texts, consecutive words being grouped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEdit {
+    Equal(String),
+    Insert(String),
+    Delete(String),
+}
+
+impl TextEdit {
+    /// Returns the word by word differences between two texts, with their longest common
+    /// subsequence of words
+    #[must_use]
+    pub fn between(older: &str, newer: &str) -> Vec<TextEdit> {
+        let older = older.split_whitespace().collect::<Vec<_>>();
+        let newer = newer.split_whitespace().collect::<Vec<_>>();
+
+        // lengths[i][j] is the length of the longest common subsequence of older[i..] and
+        // newer[j..]
+        let mut lengths = vec![vec![0_usize; newer.len() + 1]; older.len() + 1];
+        for i in (0..older.len()).rev() {
+            for j in (0..newer.len()).rev() {
+                lengths[i][j] = if older[i] == newer[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let mut edits = Vec::new();
+
+        let (mut i, mut j) = (0, 0);
+        while i < older.len() || j < newer.len() {
+            if i < older.len() && j < newer.len() && older[i] == newer[j] {
+                TextEdit::push(&mut edits, TextEdit::Equal(older[i].to_string()));
+                i += 1;
+                j += 1;
+            } else if j < newer.len()
+                && (i == older.len() || lengths[i][j + 1] >= lengths[i + 1][j])
+            {
+                TextEdit::push(&mut edits, TextEdit::Insert(newer[j].to_string()));
+                j += 1;
+            } else {
+                TextEdit::push(&mut edits, TextEdit::Delete(older[i].to_string()));
+                i += 1;
+            }
+        }
+
+        edits
+    }
+
+    /// Appends a word to the edits, grouping it with the previous words of the same edit
+    fn push(edits: &mut Vec<TextEdit>, edit: TextEdit) {
+        let grouped = match (edits.last_mut(), &edit) {
+            (Some(TextEdit::Equal(words)), TextEdit::Equal(word))
+            | (Some(TextEdit::Insert(words)), TextEdit::Insert(word))
+            | (Some(TextEdit::Delete(words)), TextEdit::Delete(word)) => {
+                words.push(' ');
+                words.push_str(word);
+                true
+            }
+            _ => false,
+        };
+
+        if !grouped {
+            edits.push(edit);
+        }
+    }
+}
+
+impl Diff {
+    /// Compares two responses of a product, e.g. two `Opmet` fetched a few minutes apart.
+    ///
+    /// Messages are the same if their category, reception date and text are. Within a location
+    /// and a category, a removed message and an added one are paired as a modified message if
+    /// they are the only ones, e.g. the METAR of an airport, or if they share at least half of
+    /// their words, e.g. two MAAs of the same warning.
+    #[must_use]
+    pub fn between<R: Response>(older: &R, newer: &R) -> Diff {
+        let mut locations = Vec::new();
+
+        for report in newer.reports() {
+            let previous = older
+                .reports()
+                .iter()
+                .find(|previous| previous.oaci() == report.oaci());
+            let messages = previous
+                .map(|previous| previous.messages().collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            locations.push(LocationDiff::between(
+                report.oaci(),
+                report.name(),
+                &messages,
+                &report.messages().collect::<Vec<_>>(),
+            ));
+        }
+
+        for report in older.reports().iter().filter(|report| {
+            !newer
+                .reports()
+                .iter()
+                .any(|newer| newer.oaci() == report.oaci())
+        }) {
+            locations.push(LocationDiff::between(
+                report.oaci(),
+                report.name(),
+                &report.messages().collect::<Vec<_>>(),
+                &[],
+            ));
+        }
+
+        locations.retain(|location| !location.changes.is_empty());
+
+        Diff { locations }
+    }
+
+    /// Returns `true` if there is no change between the responses
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+}
+
+impl LocationDiff {
+    fn between(oaci: &str, name: &str, older: &[Message], newer: &[Message]) -> LocationDiff {
+        let mut removed = older
+            .iter()
+            .filter(|message| !newer.contains(message))
+            .cloned()
+            .collect::<Vec<_>>();
+        let added = newer
+            .iter()
+            .filter(|message| !older.contains(message))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut changes = Vec::new();
+
+        for message in added {
+            let candidates = removed
+                .iter()
+                .enumerate()
+                .filter(|(_, removed)| removed.category == message.category)
+                .collect::<Vec<_>>();
+            let single = candidates.len() == 1
+                && !newer.iter().any(|other| {
+                    other != &message
+                        && other.category == message.category
+                        && !older.contains(other)
+                });
+
+            let paired = candidates
+                .into_iter()
+                .map(|(index, removed)| (index, similarity(removed, &message)))
+                .filter(|&(_, similarity)| single || similarity >= SIMILARITY)
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(index, _)| index);
+            let paired = paired.map(|index| removed.remove(index));
+
+            changes.push(match paired {
+                Some(previous) => MessageDiff {
+                    kind: DiffKind::Modified,
+                    category: message.category.clone(),
+                    edits: TextEdit::between(
+                        previous.text.as_deref().unwrap_or_default(),
+                        message.text.as_deref().unwrap_or_default(),
+                    ),
+                    older: Some(previous),
+                    newer: Some(message),
+                },
+                None => MessageDiff {
+                    kind: DiffKind::Added,
+                    category: message.category.clone(),
+                    edits: TextEdit::between("", message.text.as_deref().unwrap_or_default()),
+                    older: None,
+                    newer: Some(message),
+                },
+            });
+        }
+
+        changes.extend(removed.into_iter().map(|message| MessageDiff {
+            kind: DiffKind::Removed,
+            category: message.category.clone(),
+            edits: TextEdit::between(message.text.as_deref().unwrap_or_default(), ""),
+            older: Some(message),
+            newer: None,
+        }));
+
+        LocationDiff {
+            oaci: oaci.to_string(),
+            name: name.to_string(),
+            changes,
+        }
+    }
+}
+
+/// Returns the share of words two messages have in common, from 0 to 1
+fn similarity(older: &Message, newer: &Message) -> f64 {
+    let words = |message: &Message| {
+        message
+            .text
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .count()
+    };
+    let total = words(older) + words(newer);
+
+    if total == 0 {
+        return 1.0;
+    }
+
+    let common = TextEdit::between(
+        older.text.as_deref().unwrap_or_default(),
+        newer.text.as_deref().unwrap_or_default(),
+    )
+    .iter()
+    .map(|edit| match edit {
+        TextEdit::Equal(words) => words.split(' ').count(),
+        _ => 0,
+    })
+    .sum::<usize>();
+
+    // Word counts of messages are far below the precision of f64
+    #[allow(clippy::cast_precision_loss)]
+    let similarity = (2 * common) as f64 / total as f64;
+
+    similarity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            TextEdit::between(
+                "METAR LFBO 232000Z 31008KT CAVOK",
+                "METAR LFBO 232030Z 31010KT CAVOK"
+            ),
+            [
+                TextEdit::Equal(String::from("METAR LFBO")),
+                TextEdit::Insert(String::from("232030Z 31010KT")),
+                TextEdit::Delete(String::from("232000Z 31008KT")),
+                TextEdit::Equal(String::from("CAVOK")),
+            ]
+        );
+
+        let data = std::fs::read_to_string("./data/opmet2.xml").unwrap();
+        let older: crate::opmet::Opmet = crate::parse(&data).unwrap();
+        assert!(older.diff(&older).is_empty());
+
+        let mut newer = older.clone();
+        newer.reports[0].metar = Some(String::from(
+            "METAR LFBO 232030Z AUTO 31010KT CAVOK 08/M02 Q1019 NOSIG=",
+        ));
+        newer.reports[0].speci = Some(String::from(
+            "SPECI LFBO 232040Z AUTO 31020G35KT 9999 TS 08/M02 Q1018=",
+        ));
+        newer.reports.remove(1);
+
+        let diff = older.diff(&newer);
+        assert_eq!(diff.locations.len(), 2);
+
+        let changes = &diff.locations[0].changes;
+        assert_eq!(diff.locations[0].oaci, "LFBO");
+        assert_eq!(
+            changes
+                .iter()
+                .map(|change| (change.kind, change.category.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                (DiffKind::Modified, String::from("METAR")),
+                (DiffKind::Added, String::from("SPECI")),
+            ]
+        );
+        assert_eq!(
+            changes[0].edits[1],
+            TextEdit::Insert(String::from("232030Z"))
+        );
+
+        assert_eq!(diff.locations[1].oaci, "LFBA");
+        assert!(diff.locations[1]
+            .changes
+            .iter()
+            .all(|change| change.kind == DiffKind::Removed));
+
+        let json = serde_json::to_string(&diff).unwrap();
+        assert_eq!(serde_json::from_str::<Diff>(&json).unwrap(), diff);
+
+        let data = std::fs::read_to_string("./data/maa.xml").unwrap();
+        let older: crate::maa::Maa = crate::parse(&data).unwrap();
+        let mut newer = older.clone();
+        let messages = &mut newer.reports[0].messages;
+        messages[0].text = Some(String::from(
+            "LFLY AD WRNG 2 VALID 151500/152200\nTS FCST.=",
+        ));
+        messages.push(crate::message::Message {
+            category: MessageCategory::Maa,
+            reception_date: Some(String::from("20240715150000")),
+            text: Some(String::from("LFLY AD WRNG 5 VALID 151600/151900\nFG OBS.=")),
+        });
+
+        let changes = &older.diff(&newer).locations[0].changes;
+        assert_eq!(
+            changes.iter().map(|change| change.kind).collect::<Vec<_>>(),
+            [DiffKind::Modified, DiffKind::Added]
+        );
+        assert_eq!(
+            changes[0].older.as_ref().unwrap().text,
+            older.reports[0].messages[0].text
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::product::Product;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,12 +6,51 @@ pub enum Error {
     #[error("Unable to deserialize data")]
     Deserialize(#[from] quick_xml::de::DeError),
 
+    #[error("Unable to access the disk cache")]
+    Cache(#[source] std::io::Error),
+
+    #[error("Unable to read or write a file")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "client")]
     #[error("Unable to fetch data")]
     Fetch(#[from] reqwest::Error),
 
+    #[cfg(feature = "storage")]
+    #[error("Unable to access the archive")]
+    Storage(#[from] rusqlite::Error),
+
     #[error("Invalid api key")]
     InvalidApiKey,
 
+    #[error("Invalid ICAO location indicator: {0}")]
+    InvalidIcao(String),
+
     #[error("Invalid options: {0}")]
     InvalidOptions(String),
+
+    #[error("Invalid geographic data: {0}")]
+    InvalidGeodata(String),
+
+    #[error("Unable to fetch data: {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Daily request budget exhausted, unable to fetch {0}")]
+    QuotaExceeded(Product),
+
+    /// Error of a request shared with the concurrent identical requests which waited for it
+    #[error(transparent)]
+    Shared(std::sync::Arc<Error>),
+}
+
+impl Error {
+    /// Returns the error itself, or the one it shares for `Error::Shared`
+    /// e.g. `matches!(e.root(), Error::InvalidApiKey)`
+    #[must_use]
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Shared(e) => e.root(),
+            e => e,
+        }
+    }
 }
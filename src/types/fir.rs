@@ -1,4 +1,22 @@
-#[derive(Debug, strum::Display)]
+use crate::fir_info::FirInfo;
+use crate::helpers::{ord_by_code, serde_from_str};
+use crate::icao_region::IcaoRegion;
+use strum::IntoEnumIterator;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::Display,
+    strum::EnumCount,
+    strum::EnumIter,
+    strum::EnumString,
+    strum::IntoStaticStr,
+)]
+#[strum(ascii_case_insensitive)]
 /// List of the world's Flight Information Regions
 /// Source: <https://en.wikipedia.org/wiki/Flight_information_region>
 ///
@@ -620,3 +638,53 @@ pub enum Fir {
     /// Shenyang ACC, China
     ZYSH,
 }
+
+impl Fir {
+    /// Returns the metadata of the FIR from the bundled dataset
+    #[must_use]
+    pub fn info(self) -> &'static FirInfo {
+        &FirInfo::all()[&self]
+    }
+
+    /// Returns the FIRs of a country, including its dependent territories
+    /// e.g. `Fir::of_country("France")`
+    #[must_use]
+    pub fn of_country(country: &str) -> Vec<Fir> {
+        Fir::iter()
+            .filter(|fir| fir.info().is_in_country(country))
+            .collect()
+    }
+
+    /// Returns the FIRs of an ICAO region
+    /// e.g. `Fir::of_region(IcaoRegion::Europe)`
+    #[must_use]
+    pub fn of_region(region: IcaoRegion) -> Vec<Fir> {
+        Fir::iter()
+            .filter(|fir| fir.info().region == region)
+            .collect()
+    }
+}
+
+serde_from_str!(Fir);
+ord_by_code!(Fir);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fir() {
+        assert_eq!("LFBB".parse::<Fir>().unwrap(), Fir::LFBB);
+        assert_eq!("lfbb".parse::<Fir>().unwrap(), Fir::LFBB);
+        assert!("LFBO".parse::<Fir>().is_err());
+
+        assert_eq!(serde_json::to_string(&Fir::LFBB).unwrap(), "\"LFBB\"");
+        assert_eq!(
+            serde_json::from_str::<Vec<Fir>>("[\"LFBB\", \"ebbu\"]").unwrap(),
+            [Fir::LFBB, Fir::EBBU]
+        );
+        assert!(serde_json::from_str::<Fir>("\"LFBO\"").is_err());
+
+        assert!(Fir::EBBU < Fir::LFBB);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::coordinates::{polygon_contains, Coordinates};
+use crate::fir::Fir;
+
+/// Boundary of a FIR, loaded from a published dataset, see
+/// `aeroweb::geodata::Geodata::load_boundaries`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirBoundary {
+    pub fir: Fir,
+
+    /// Polygons of the FIR, each made of its exterior ring followed by its holes. The last
+    /// vertex of a ring is implicitly linked to the first one.
+    pub polygons: Vec<Vec<Vec<Coordinates>>>,
+}
+
+impl FirBoundary {
+    /// Returns `true` if the position is inside one of the polygons and outside its holes, with
+    /// the even-odd rule on the latitudes and longitudes
+    #[must_use]
+    pub fn contains(&self, position: Coordinates) -> bool {
+        self.polygons.iter().any(|rings| {
+            rings
+                .iter()
+                .filter(|ring| polygon_contains(ring, position))
+                .count()
+                % 2
+                == 1
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fir_boundary() {
+        let square = |latitude: f64, longitude: f64, size: f64| {
+            vec![
+                Coordinates::new(latitude, longitude),
+                Coordinates::new(latitude, longitude + size),
+                Coordinates::new(latitude + size, longitude + size),
+                Coordinates::new(latitude + size, longitude),
+            ]
+        };
+
+        // A square with a hole, and a smaller island
+        let boundary = FirBoundary {
+            fir: Fir::LFMM,
+            polygons: vec![
+                vec![square(0.0, 0.0, 1.0)],
+                vec![square(10.0, 10.0, 4.0), square(11.0, 11.0, 2.0)],
+            ],
+        };
+
+        assert!(boundary.contains(Coordinates::new(0.5, 0.5)));
+        assert!(boundary.contains(Coordinates::new(10.5, 10.5)));
+        assert!(!boundary.contains(Coordinates::new(12.0, 12.0)));
+        assert!(!boundary.contains(Coordinates::new(5.0, 5.0)));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::fir::Fir;
+use crate::icao_region::IcaoRegion;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Bundled dataset of the FIRs, one line per `Fir`.
+///
+/// ACC names and countries follow the list of flight information regions the `Fir` enum is
+/// extracted from. ICAO regions are derived from the nationality letters of the codes. The UIRs
+/// are only known for the European and North African FIRs so far, the field is left empty for
+/// the others.
+const DATASET: &str = include_str!("firs.csv");
+
+static FIRS: OnceLock<HashMap<Fir, FirInfo>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FirInfo {
+    pub fir: Fir,
+
+    /// Area control center in charge of the FIR
+    /// e.g. Bordeaux ACC, Shanwick Oceanic OCA
+    pub acc: &'static str,
+
+    /// Country, followed by the sovereign state for dependent territories
+    /// e.g. France, French Polynesia (France)
+    pub country: &'static str,
+
+    pub region: IcaoRegion,
+
+    /// `true` if an Upper Information Region covers the airspace above the FIR, `None` if it is
+    /// not known
+    pub uir: Option<bool>,
+}
+
+impl FirInfo {
+    /// Parses a line of the dataset, e.g. `LFBB;Bordeaux ACC;France;EUR;true`, or
+    /// `FCCC;Brazzaville ACC;Republic of the Congo;AFI;` when the UIR is not known
+    fn parse(line: &'static str) -> Option<FirInfo> {
+        let mut fields = line.split(';');
+        let mut next = || fields.next();
+
+        Some(FirInfo {
+            fir: next()?.parse().ok()?,
+            acc: next()?,
+            country: next()?,
+            region: next()?.parse().ok()?,
+            uir: match next()? {
+                "" => None,
+                uir => Some(uir.parse().ok()?),
+            },
+        })
+    }
+
+    /// Returns `true` if the FIR belongs to the country or one of its dependent territories,
+    /// ignoring case, e.g. `France` matches `French Guiana (France)`
+    #[must_use]
+    pub fn is_in_country(&self, country: &str) -> bool {
+        let sovereign = self
+            .country
+            .rsplit_once(" (")
+            .and_then(|(_, sovereign)| sovereign.strip_suffix(')'));
+
+        self.country.eq_ignore_ascii_case(country)
+            || sovereign.is_some_and(|sovereign| sovereign.eq_ignore_ascii_case(country))
+    }
+
+    /// Returns the FIRs of the dataset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a line of the bundled dataset is malformed, which is checked by the tests.
+    ///
+    pub(crate) fn all() -> &'static HashMap<Fir, FirInfo> {
+        FIRS.get_or_init(|| {
+            DATASET
+                .lines()
+                .skip(1)
+                .map(|line| {
+                    let info = FirInfo::parse(line)
+                        .unwrap_or_else(|| panic!("Invalid FIR in the dataset: {line}"));
+
+                    (info.fir, info)
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::{EnumCount, IntoEnumIterator};
+
+    #[test]
+    fn test_fir_info() {
+        assert_eq!(FirInfo::all().len(), Fir::COUNT);
+
+        // The dataset follows the order of the enum
+        let codes = DATASET
+            .lines()
+            .skip(1)
+            .map(|line| line.split(';').next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            Fir::iter().map(|fir| fir.to_string()).collect::<Vec<_>>()
+        );
+
+        let info = Fir::LFBB.info();
+        assert_eq!(info.acc, "Bordeaux ACC");
+        assert_eq!(info.country, "France");
+        assert_eq!(info.region, IcaoRegion::Europe);
+        assert_eq!(info.uir, Some(true));
+        assert_eq!(Fir::LPPO.info().uir, Some(false));
+
+        let info = Fir::FCCC.info();
+        assert_eq!(info.acc, "Brazzaville ACC");
+        assert_eq!(info.country, "Republic of the Congo");
+        assert_eq!(info.region, IcaoRegion::Afi);
+        assert!(info.uir.is_none());
+
+        assert_eq!(Fir::EGGX.info().region, IcaoRegion::NorthAtlantic);
+        assert!(Fir::SOOO.info().is_in_country("france"));
+        assert!(Fir::SOOO.info().is_in_country("French Guiana (France)"));
+        assert!(!Fir::SOOO.info().is_in_country("Guiana"));
+
+        assert_eq!(
+            Fir::of_country("France"),
+            [
+                Fir::LFBB,
+                Fir::LFEE,
+                Fir::LFFF,
+                Fir::LFMM,
+                Fir::LFRR,
+                Fir::NTTT,
+                Fir::NWWX,
+                Fir::SOOO
+            ]
+        );
+        assert_eq!(Fir::of_country("Germany").len(), 3);
+        assert!(Fir::of_country("Atlantis").is_empty());
+
+        let europe = Fir::of_region(IcaoRegion::Europe);
+        assert!(europe.contains(&Fir::EBBU));
+        assert!(!europe.contains(&Fir::EGGX));
+        assert_eq!(
+            IcaoRegion::iter()
+                .map(|region| Fir::of_region(region).len())
+                .sum::<usize>(),
+            Fir::COUNT
+        );
+        assert_eq!("eur".parse::<IcaoRegion>().unwrap(), IcaoRegion::Europe);
+        assert_eq!(IcaoRegion::AsiaPacific.to_string(), "APAC");
+    }
+}
@@ -0,0 +1,585 @@
+use crate::airport::Airport;
+use crate::airport_info::AirportInfo;
+use crate::coordinates::{sample_route, Coordinates};
+use crate::error::Error;
+use crate::fir::Fir;
+use crate::fir_boundary::FirBoundary;
+use crate::opmet::Opmet;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use strum::IntoEnumIterator;
+
+/// Distance in nautical miles between the positions sampled along a route
+const ROUTE_STEP: f64 = 10.0;
+
+/// Positions and elevations of the airports, extracted from the `airports.csv` of OurAirports
+/// with `data/geodata/extract_airports.py`
+const AIRPORT_POSITIONS: &str = include_str!("airport_positions.csv");
+
+/// Positions of the airports, bundled with the library and optionally replaced with
+/// `load_airports`, and boundaries of the FIRs, loaded from a published dataset, e.g. the
+/// `Boundaries.geojson` of the VATSpy data project:
+/// <https://github.com/vatsimnetwork/vatspy-data-project>, along with the reports the airports
+/// issue, learnt from the OPMET responses
+#[derive(Debug, Clone)]
+pub struct Geodata {
+    airports: HashMap<Airport, AirportPosition>,
+    boundaries: BTreeMap<Fir, FirBoundary>,
+    reporting: HashMap<Airport, Reporting>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AirportPosition {
+    pub airport: Airport,
+
+    /// Aerodrome reference point
+    pub coordinates: Coordinates,
+
+    /// Elevation in feet, `None` if the dataset doesn't give it
+    pub elevation: Option<i32>,
+}
+
+/// Reports issued by an airport
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reporting {
+    /// `true` if the airport issues METARs
+    pub metar: bool,
+
+    /// `true` if the airport issues TAFs
+    pub taf: bool,
+}
+
+impl Default for Geodata {
+    fn default() -> Geodata {
+        Geodata::new()
+    }
+}
+
+impl Geodata {
+    /// Returns the geodata with the bundled positions of the airports, and no FIR boundary
+    ///
+    /// # Panics
+    ///
+    /// Panics if a position of the bundled dataset is malformed, which is checked by the tests.
+    ///
+    #[must_use]
+    pub fn new() -> Geodata {
+        let mut geodata = Geodata {
+            airports: HashMap::new(),
+            boundaries: BTreeMap::new(),
+            reporting: HashMap::new(),
+        };
+        geodata
+            .load_airports(AIRPORT_POSITIONS)
+            .unwrap_or_else(|e| panic!("Invalid airport positions in the dataset: {e}"));
+
+        geodata
+    }
+
+    /// Loads the positions of the airports from the `airports.csv` file of OurAirports, and
+    /// returns the number of airports loaded, e.g. to use a more recent release than the bundled
+    /// one. The rows whose `ident` is not an `Airport` are skipped, the positions already loaded
+    /// are replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column is missing or the position of an airport cannot be parsed.
+    ///
+    pub fn load_airports(&mut self, csv: &str) -> Result<usize, Error> {
+        let mut lines = csv.lines();
+        let header = csv_fields(lines.next().unwrap_or_default());
+        let column = |name: &str| {
+            header
+                .iter()
+                .position(|field| field == name)
+                .ok_or_else(|| Error::InvalidGeodata(format!("Missing column {name}")))
+        };
+        let (ident, latitude, longitude, elevation) = (
+            column("ident")?,
+            column("latitude_deg")?,
+            column("longitude_deg")?,
+            column("elevation_ft")?,
+        );
+
+        let mut loaded = 0;
+
+        for line in lines.filter(|line| !line.is_empty()) {
+            let fields = csv_fields(line);
+            let field = |index: usize| fields.get(index).map_or("", String::as_str);
+
+            let Ok(airport) = field(ident).parse::<Airport>() else {
+                continue;
+            };
+            let invalid = || Error::InvalidGeodata(format!("Invalid position of {airport}"));
+
+            self.airports.insert(
+                airport,
+                AirportPosition {
+                    airport,
+                    coordinates: Coordinates::new(
+                        field(latitude).parse().map_err(|_| invalid())?,
+                        field(longitude).parse().map_err(|_| invalid())?,
+                    ),
+                    elevation: match field(elevation) {
+                        "" => None,
+                        elevation => Some(elevation.parse().map_err(|_| invalid())?),
+                    },
+                },
+            );
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+
+    /// Loads the boundaries of the FIRs from a GeoJSON feature collection of polygons and
+    /// multipolygons, and returns the number of FIRs loaded. The code of the FIR of a feature is
+    /// read from its `property`, e.g. `id` for VATSpy. The features whose code is not a `Fir`
+    /// are skipped, those sharing a code are merged, and the boundaries already loaded are
+    /// replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the GeoJSON cannot be parsed or a ring has less than 3 vertices.
+    ///
+    pub fn load_boundaries(&mut self, geojson: &str, property: &str) -> Result<usize, Error> {
+        let collection: FeatureCollection =
+            serde_json::from_str(geojson).map_err(|e| Error::InvalidGeodata(e.to_string()))?;
+
+        let mut loaded = BTreeMap::<Fir, FirBoundary>::new();
+
+        for feature in collection.features {
+            let Some(fir) = feature
+                .properties
+                .get(property)
+                .and_then(serde_json::Value::as_str)
+                .and_then(|code| code.parse::<Fir>().ok())
+            else {
+                continue;
+            };
+
+            let polygons = match feature.geometry {
+                Some(Geometry::Polygon { coordinates }) => vec![coordinates],
+                Some(Geometry::MultiPolygon { coordinates }) => coordinates,
+                Some(Geometry::Other) | None => continue,
+            };
+
+            let mut boundary = loaded.remove(&fir).unwrap_or(FirBoundary {
+                fir,
+                polygons: vec![],
+            });
+
+            for rings in polygons {
+                boundary.polygons.push(
+                    rings
+                        .iter()
+                        .map(|ring| ring_coordinates(ring))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| {
+                            Error::InvalidGeodata(format!("Invalid boundary of {fir}"))
+                        })?,
+                );
+            }
+
+            loaded.insert(fir, boundary);
+        }
+
+        let count = loaded.len();
+        self.boundaries.extend(loaded);
+
+        Ok(count)
+    }
+
+    /// Records the reports issued by the airports of an OPMET response. An airport is reporting
+    /// METARs or TAFs once a response carried one of them, a missing report not clearing it.
+    /// e.g. `geodata.record_reporting(&client.get_opmet(options).await?)`
+    pub fn record_reporting(&mut self, opmet: &Opmet) {
+        for report in &opmet.reports {
+            let Ok(airport) = report.oaci.parse::<Airport>() else {
+                continue;
+            };
+            let reporting = self.reporting.entry(airport).or_default();
+
+            reporting.metar |= report.metar.is_some() || report.speci.is_some();
+            reporting.taf |= report.taf.is_some();
+        }
+    }
+
+    /// Returns the reports issued by an airport, `None` if it was not part of any OPMET response
+    /// given to `record_reporting`
+    /// e.g. `geodata.reporting(Airport::LFBO).is_some_and(|reporting| reporting.taf)`
+    #[must_use]
+    pub fn reporting(&self, airport: Airport) -> Option<Reporting> {
+        self.reporting.get(&airport).copied()
+    }
+
+    /// Returns the airports whose position was not loaded, which are left out of the
+    /// geographic searches
+    #[must_use]
+    pub fn missing(&self) -> Vec<Airport> {
+        Airport::iter()
+            .filter(|airport| !self.airports.contains_key(airport))
+            .collect()
+    }
+
+    /// Returns the position of an airport, `None` if it was not loaded
+    #[must_use]
+    pub fn position(&self, airport: Airport) -> Option<&AirportPosition> {
+        self.airports.get(&airport)
+    }
+
+    /// Returns the great-circle distance in nautical miles between an airport and a position,
+    /// `None` if the position of the airport was not loaded
+    #[must_use]
+    pub fn distance(&self, airport: Airport, position: Coordinates) -> Option<f64> {
+        Some(self.position(airport)?.coordinates.distance(position))
+    }
+
+    /// Returns the initial true bearing in degrees, from 0 (north) to 360, from a position to an
+    /// airport, `None` if the position of the airport was not loaded
+    #[must_use]
+    pub fn bearing(&self, airport: Airport, position: Coordinates) -> Option<f64> {
+        Some(position.bearing(self.position(airport)?.coordinates))
+    }
+
+    /// Returns the boundary of a FIR, `None` if it was not loaded
+    #[must_use]
+    pub fn boundary(&self, fir: Fir) -> Option<&FirBoundary> {
+        self.boundaries.get(&fir)
+    }
+
+    /// Returns the first FIR, ordered by code, whose loaded boundary contains the position
+    #[must_use]
+    pub fn containing(&self, position: Coordinates) -> Option<Fir> {
+        self.boundaries
+            .values()
+            .find(|boundary| boundary.contains(position))
+            .map(|boundary| boundary.fir)
+    }
+
+    /// Returns the FIRs crossed by a route, in the order they are entered, each FIR once.
+    /// The great circles between the points are sampled every 10 NM, so a FIR only clipped by a
+    /// leg may be missed.
+    /// e.g. `RequestOptions::for_firs(&geodata.crossed(&route))`
+    #[must_use]
+    pub fn crossed(&self, route: &[Coordinates]) -> Vec<Fir> {
+        let mut firs = Vec::new();
+
+        for (position, _) in sample_route(route, ROUTE_STEP) {
+            if let Some(fir) = self.containing(position) {
+                if !firs.contains(&fir) {
+                    firs.push(fir);
+                }
+            }
+        }
+
+        firs
+    }
+
+    /// Returns the Flight Information Region containing an airport, `None` if its position or
+    /// the boundary of its FIR was not loaded
+    /// e.g. `geodata.fir(Airport::LFBO) == Some(Fir::LFBB)`
+    #[must_use]
+    pub fn fir(&self, airport: Airport) -> Option<Fir> {
+        self.containing(self.position(airport)?.coordinates)
+    }
+
+    /// Same as `fir`, with an error for the airports whose FIR is unknown
+    pub(crate) fn require_fir(&self, airport: Airport) -> Result<Fir, Error> {
+        self.fir(airport)
+            .ok_or_else(|| Error::InvalidGeodata(format!("The FIR of {airport} is unknown")))
+    }
+
+    /// Returns the `count` airports nearest to a position among those matching `filter`, the
+    /// nearest first. Airports whose position was not loaded are skipped, see `missing`.
+    /// e.g. `geodata.nearest(position, 5, |info| geodata.reporting(info.airport).is_some_and(|r| r.taf))`
+    #[must_use]
+    pub fn nearest(
+        &self,
+        position: Coordinates,
+        count: usize,
+        filter: impl Fn(&AirportInfo) -> bool,
+    ) -> Vec<Airport> {
+        self.by_distance(position, filter)
+            .into_iter()
+            .take(count)
+            .map(|(airport, _)| airport)
+            .collect()
+    }
+
+    /// Returns the airports within `radius` nautical miles of a position among those matching
+    /// `filter`, the nearest first. Airports whose position was not loaded are skipped, see
+    /// `missing`.
+    /// e.g. `geodata.within(position, 50.0, |info| !info.military)`
+    #[must_use]
+    pub fn within(
+        &self,
+        position: Coordinates,
+        radius: f64,
+        filter: impl Fn(&AirportInfo) -> bool,
+    ) -> Vec<Airport> {
+        self.by_distance(position, filter)
+            .into_iter()
+            .take_while(|(_, distance)| *distance <= radius)
+            .map(|(airport, _)| airport)
+            .collect()
+    }
+
+    /// Returns the geodata loaded from the fixtures of `data/geodata`
+    #[cfg(test)]
+    pub(crate) fn fixture() -> Geodata {
+        let mut geodata = Geodata::new();
+        let csv = std::fs::read_to_string("./data/geodata/airports.csv").unwrap();
+        geodata.load_airports(&csv).unwrap();
+        let geojson = std::fs::read_to_string("./data/geodata/firs.geojson").unwrap();
+        geodata.load_boundaries(&geojson, "id").unwrap();
+
+        geodata
+    }
+
+    /// Returns the airports with a known position matching `filter`, sorted by distance
+    fn by_distance(
+        &self,
+        position: Coordinates,
+        filter: impl Fn(&AirportInfo) -> bool,
+    ) -> Vec<(Airport, f64)> {
+        let mut airports = Airport::iter()
+            .filter(|airport| filter(airport.info()))
+            .filter_map(|airport| Some((airport, self.distance(airport, position)?)))
+            .collect::<Vec<_>>();
+
+        airports.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        airports
+    }
+}
+
+#[derive(Deserialize)]
+struct FeatureCollection {
+    features: Vec<Feature>,
+}
+
+#[derive(Deserialize)]
+struct Feature {
+    #[serde(default)]
+    properties: serde_json::Map<String, serde_json::Value>,
+    geometry: Option<Geometry>,
+}
+
+/// Geometry of a feature, as longitudes and latitudes
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Geometry {
+    Polygon {
+        coordinates: Vec<Vec<Vec<f64>>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<Vec<f64>>>>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Converts a GeoJSON ring, whose last position repeats the first one, into its vertices
+fn ring_coordinates(ring: &[Vec<f64>]) -> Option<Vec<Coordinates>> {
+    let mut points = ring
+        .iter()
+        .map(|position| match position[..] {
+            [longitude, latitude, ..] => Some(Coordinates::new(latitude, longitude)),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    (points.len() >= 3).then_some(points)
+}
+
+/// Splits a line of a CSV file separated by commas, whose fields may be quoted, e.g.
+/// `"LFBO","Toulouse-Blagnac Airport",43.629101,1.36382`
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::EnumCount;
+
+    #[test]
+    fn test_geodata() {
+        assert_eq!(
+            csv_fields(r#"1,"LFBO","Toulouse, ""Blagnac""",,499"#),
+            ["1", "LFBO", "Toulouse, \"Blagnac\"", "", "499"]
+        );
+
+        // The bundled positions are replaced by the loaded ones
+        let mut geodata = Geodata::new();
+        assert_eq!(
+            geodata.missing().len(),
+            Airport::COUNT + 1 - AIRPORT_POSITIONS.lines().count()
+        );
+        let csv = "ident,latitude_deg,longitude_deg,elevation_ft\nLFBO,43.6,1.4,\n";
+        assert_eq!(geodata.load_airports(csv).unwrap(), 1);
+        assert!(geodata.position(Airport::LFBO).unwrap().elevation.is_none());
+        let csv = std::fs::read_to_string("./data/geodata/airports.csv").unwrap();
+        assert_eq!(geodata.load_airports(&csv).unwrap(), 29);
+        assert!(geodata.fir(Airport::LFBO).is_none());
+        let geojson = std::fs::read_to_string("./data/geodata/firs.geojson").unwrap();
+        assert_eq!(geodata.load_boundaries(&geojson, "id").unwrap(), 9);
+        assert!(geodata.position(Airport::LFML).unwrap().elevation.is_none());
+
+        let toulouse = geodata.position(Airport::LFBO).unwrap();
+        assert_eq!(toulouse.elevation, Some(499));
+        let toulouse = toulouse.coordinates;
+        assert_eq!(geodata.distance(Airport::LFBO, toulouse), Some(0.0));
+        assert!(geodata.distance(Airport::LFHN, toulouse).is_none());
+
+        assert_eq!(
+            geodata.nearest(toulouse, 2, |_| true),
+            [Airport::LFBO, Airport::LFBF]
+        );
+        assert_eq!(
+            geodata.nearest(toulouse, 2, |info| !info.military),
+            [Airport::LFBO, Airport::LFCI]
+        );
+        assert_eq!(
+            geodata.within(toulouse, 20.0, |_| true),
+            [Airport::LFBO, Airport::LFBF]
+        );
+        assert!(geodata
+            .within(toulouse, 50.0, |_| true)
+            .contains(&Airport::LFBA));
+
+        for (airport, fir) in [
+            (Airport::LFBO, Fir::LFBB),
+            (Airport::LFRB, Fir::LFRR),
+            (Airport::LFPG, Fir::LFFF),
+            (Airport::LFSB, Fir::LFEE),
+            (Airport::LFKJ, Fir::LFMM),
+            (Airport::LFLL, Fir::LFMM),
+        ] {
+            assert_eq!(geodata.fir(airport), Some(fir), "{airport}");
+        }
+
+        // Papeete, Cayenne, London Heathrow
+        assert_eq!(
+            geodata.containing(Coordinates::new(-17.55, -149.6)),
+            Some(Fir::NTTT)
+        );
+        assert_eq!(
+            geodata.containing(Coordinates::new(4.82, -52.36)),
+            Some(Fir::SOOO)
+        );
+        assert_eq!(
+            geodata.containing(Coordinates::new(51.47, -0.45)),
+            Some(Fir::EGTT)
+        );
+        assert!(geodata.containing(Coordinates::new(0.0, 0.0)).is_none());
+        assert_eq!(geodata.boundary(Fir::LFMM).unwrap().polygons.len(), 2);
+        assert!(geodata.boundary(Fir::KZNY).is_none());
+
+        // Toulouse, Paris, Brussels
+        let route = [
+            toulouse,
+            geodata.position(Airport::LFPG).unwrap().coordinates,
+            Coordinates::new(50.9, 4.48),
+        ];
+        assert_eq!(
+            geodata.crossed(&route),
+            [Fir::LFBB, Fir::LFFF, Fir::LFEE, Fir::EBBU]
+        );
+        assert!(geodata.crossed(&[]).is_empty());
+
+        assert!(geodata.fir(Airport::LFHN).is_none());
+        assert!(matches!(
+            geodata.require_fir(Airport::LFHN),
+            Err(Error::InvalidGeodata(_))
+        ));
+
+        let missing = geodata.missing();
+        assert_eq!(missing.len(), Airport::COUNT - 29);
+        assert!(missing.contains(&Airport::LFHN));
+        assert!(!missing.contains(&Airport::LFBO));
+
+        // Agen is northwest of Toulouse
+        let bearing = geodata.bearing(Airport::LFBA, toulouse).unwrap();
+        assert!((305.0..325.0).contains(&bearing), "{bearing}");
+        assert!(geodata.bearing(Airport::LFHN, toulouse).is_none());
+
+        let opmet = std::fs::read_to_string("./data/opmet2.xml").unwrap();
+        geodata.record_reporting(&crate::parse(&opmet).unwrap());
+        assert_eq!(
+            geodata.reporting(Airport::LFBO),
+            Some(Reporting {
+                metar: true,
+                taf: true
+            })
+        );
+        assert!(geodata.reporting(Airport::LFBF).is_none());
+        assert_eq!(
+            geodata.nearest(toulouse, 5, |info| geodata
+                .reporting(info.airport)
+                .is_some_and(|reporting| reporting.taf)),
+            [Airport::LFBO, Airport::LFBA]
+        );
+
+        // The features sharing a code are merged
+        let mut geodata = Geodata::new();
+        let feature = |longitude: f64| {
+            format!(
+                r#"{{"type":"Feature","properties":{{"id":"LFBB"}},"geometry":{{"type":"Polygon",
+                "coordinates":[[[{longitude},44],[{},44],[{},45],[{longitude},44]]]}}}}"#,
+                longitude + 1.0,
+                longitude + 1.0
+            )
+        };
+        let geojson = format!(
+            r#"{{"type":"FeatureCollection","features":[{},{}]}}"#,
+            feature(0.0),
+            feature(2.0)
+        );
+        assert_eq!(geodata.load_boundaries(&geojson, "id").unwrap(), 1);
+        assert_eq!(geodata.boundary(Fir::LFBB).unwrap().polygons.len(), 2);
+        assert_eq!(geodata.boundary(Fir::LFBB).unwrap().polygons[0][0].len(), 3);
+        assert_eq!(geodata.load_boundaries(&geojson, "name").unwrap(), 0);
+        assert!(matches!(
+            geodata.load_boundaries(r#"{"type":"Feature"}"#, "id"),
+            Err(Error::InvalidGeodata(_))
+        ));
+        assert!(matches!(
+            geodata.load_boundaries(&geojson.replace("[[[0,44],[1,44],", "[[["), "id"),
+            Err(Error::InvalidGeodata(_))
+        ));
+
+        assert!(matches!(
+            Geodata::new().load_airports("ident,latitude_deg\nLFBO,43.6"),
+            Err(Error::InvalidGeodata(_))
+        ));
+        assert!(matches!(
+            Geodata::new()
+                .load_airports("ident,latitude_deg,longitude_deg,elevation_ft\nLFBO,north,1.3,"),
+            Err(Error::InvalidGeodata(_))
+        ));
+    }
+}
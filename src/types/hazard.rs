@@ -0,0 +1,546 @@
+use crate::coordinates::{polygon_contains, sample_route, Coordinates};
+use crate::fir::Fir;
+use crate::geodata::Geodata;
+use crate::message_category::MessageCategory;
+use crate::report::Report;
+use serde::{Deserialize, Serialize};
+
+/// Distance in nautical miles between the positions sampled along a route
+const ROUTE_STEP: f64 = 1.0;
+
+/// Number of vertices of the polygons approximating the circular areas
+const CIRCLE_VERTICES: u32 = 36;
+
+/// Kilometers per nautical mile
+const KM_PER_NM: f64 = 1.852;
+
+/// Type of the message a hazard is decoded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display)]
+pub enum HazardKind {
+    Sigmet,
+    Airmet,
+    VolcanicAsh,
+    TropicalCyclone,
+}
+
+/// Area of a SIGMET, AIRMET, volcanic ash cloud or tropical cyclone, decoded from the text of
+/// the message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hazard {
+    pub kind: HazardKind,
+
+    /// FIR, VAAC or TCAC of the message
+    /// e.g. LFMM, ADRM
+    pub location: String,
+
+    /// Rings of the area, a position being inside if it is within an odd number of them, e.g. a
+    /// single polygon, or the polygons of a FIR with their holes. The last vertex of a ring is
+    /// implicitly linked to the first one.
+    pub area: Vec<Vec<Coordinates>>,
+
+    /// Lowest flight level, `None` from the surface
+    pub base: Option<u16>,
+
+    /// Highest flight level, `None` if unknown or unlimited
+    pub top: Option<u16>,
+
+    /// Start of the validity (or time of the observation) as day, hour and minute UTC
+    /// e.g. 231800
+    pub valid_from: Option<String>,
+
+    /// End of the validity (or time of the first forecast) as day, hour and minute UTC
+    /// e.g. 232200
+    pub valid_to: Option<String>,
+
+    /// Message the hazard is decoded from
+    pub text: String,
+}
+
+impl Hazard {
+    /// Decodes the area, levels and validity of a message, `None` if it has no area this
+    /// library understands.
+    ///
+    /// Areas are read from `WI` polygons (`WI N4215 E00315 - N4215 E00230 - ...`), circles
+    /// (`WI 150NM OF TC CENTRE`, `WI 30KM OF N4500 E00300`) and `ENTIRE FIR` for the FIRs whose
+    /// boundary is loaded in `geodata`, with all its polygons, e.g. Corsica for LFMM.
+    /// Volcanic ash advisories use the observed or estimated cloud, tropical cyclone advisories
+    /// the cumulonimbus area.
+    #[must_use]
+    pub fn parse(
+        kind: HazardKind,
+        location: &str,
+        text: &str,
+        geodata: Option<&Geodata>,
+    ) -> Option<Hazard> {
+        let tokens = text
+            .split_whitespace()
+            .map(|token| token.trim_end_matches('='))
+            .collect::<Vec<_>>();
+
+        let (section, valid_from, valid_to) = match kind {
+            HazardKind::Sigmet | HazardKind::Airmet => {
+                let validity = after(&tokens, &["VALID"]).and_then(|tokens| tokens.first());
+                let (from, to) = validity
+                    .and_then(|validity| validity.split_once('/'))
+                    .map_or((None, None), |(from, to)| {
+                        (Some(from.to_string()), Some(to.to_string()))
+                    });
+
+                (&tokens[..], from, to)
+            }
+            HazardKind::VolcanicAsh => (
+                section(&tokens, &["VA", "CLD:"], "FCST")?,
+                after(&tokens, &["VA", "DTG:"]).and_then(time),
+                after(&tokens, &["FCST", "VA", "CLD"]).and_then(|tokens| time(tokens.get(2..)?)),
+            ),
+            HazardKind::TropicalCyclone => (
+                section(&tokens, &["CB:"], "MOV:")?,
+                after(&tokens, &["PSN:"]).and_then(time),
+                after(&tokens, &["FCST", "PSN"]).and_then(|tokens| time(tokens.get(2..)?)),
+            ),
+        };
+
+        let area = area(section, location, geodata).or_else(|| {
+            // Circles around the centre of the cyclone, given before the area
+            let radius = radius(after(section, &["WI"])?)?;
+            let centre = coordinates(after(&tokens, &["PSN:"])?.get(1..)?)
+                .or_else(|| coordinates(after(&tokens, &["PSN"])?))?;
+
+            Some(vec![circle(centre, radius)])
+        })?;
+
+        let (base, top) = levels(section);
+
+        Some(Hazard {
+            kind,
+            location: location.to_string(),
+            area,
+            base,
+            top,
+            valid_from,
+            valid_to,
+            text: text.to_string(),
+        })
+    }
+
+    /// Decodes the hazards of the SIGMET, AIRMET, VAA and TCA messages of reports, leaving out
+    /// the messages without an area this library understands
+    pub(crate) fn from_reports<R: Report>(reports: &[R], geodata: Option<&Geodata>) -> Vec<Hazard> {
+        reports
+            .iter()
+            .flat_map(|report| {
+                report.messages().flat_map(|message| {
+                    let kind = match message.category {
+                        MessageCategory::Sigmet => Some(HazardKind::Sigmet),
+                        MessageCategory::Airmet => Some(HazardKind::Airmet),
+                        MessageCategory::Vaa => Some(HazardKind::VolcanicAsh),
+                        MessageCategory::Tca => Some(HazardKind::TropicalCyclone),
+                        _ => None,
+                    };
+                    let text = message.text.unwrap_or_default();
+
+                    // A text may hold several messages, each ending with `=`
+                    text.split_terminator('=')
+                        .filter_map(|text| {
+                            Hazard::parse(kind?, report.oaci(), text.trim(), geodata)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the position at the flight level is inside the hazard
+    #[must_use]
+    pub fn contains(&self, position: Coordinates, level: u16) -> bool {
+        self.base.is_none_or(|base| level >= base)
+            && self.top.is_none_or(|top| level <= top)
+            && self
+                .area
+                .iter()
+                .filter(|ring| polygon_contains(ring, position))
+                .count()
+                % 2
+                == 1
+    }
+
+    /// Returns the portions of a route inside the hazard, in the order they are flown.
+    /// The route is a list of points with the planned flight level at each of them, the level
+    /// changing linearly between two points, and follows the great circles between them.
+    #[must_use]
+    pub fn intersections(&self, route: &[(Coordinates, u16)]) -> Vec<Intersection> {
+        let positions = route
+            .iter()
+            .map(|&(position, _)| position)
+            .collect::<Vec<_>>();
+        let samples = sample_route(&positions, ROUTE_STEP);
+
+        let mut intersections = Vec::<Intersection>::new();
+        let mut inside = false;
+
+        for (position, distance) in samples {
+            if !self.contains(position, level_at(route, distance)) {
+                inside = false;
+                continue;
+            }
+
+            match intersections.last_mut() {
+                Some(intersection) if inside => {
+                    intersection.exit = position;
+                    intersection.exit_distance = distance;
+                }
+                _ => intersections.push(Intersection {
+                    hazard: self.clone(),
+                    entry: position,
+                    exit: position,
+                    entry_distance: distance,
+                    exit_distance: distance,
+                }),
+            }
+
+            inside = true;
+        }
+
+        intersections
+    }
+}
+
+/// Portion of a route inside a hazard
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intersection {
+    /// Hazard crossed, its validity giving the time window of the intersection
+    pub hazard: Hazard,
+
+    pub entry: Coordinates,
+
+    pub exit: Coordinates,
+
+    /// Distance along the route in nautical miles, from the first point, of the entry
+    pub entry_distance: f64,
+
+    /// Distance along the route in nautical miles, from the first point, of the exit
+    pub exit_distance: f64,
+}
+
+impl Intersection {
+    /// Returns the portions of a route inside the hazards, ordered along the route
+    /// See `aeroweb::hazard::Hazard::intersections`
+    #[must_use]
+    pub fn find(route: &[(Coordinates, u16)], hazards: &[Hazard]) -> Vec<Intersection> {
+        let mut intersections = hazards
+            .iter()
+            .flat_map(|hazard| hazard.intersections(route))
+            .collect::<Vec<_>>();
+
+        intersections.sort_by(|a, b| a.entry_distance.total_cmp(&b.entry_distance));
+
+        intersections
+    }
+}
+
+/// Returns the planned flight level at a distance along the route
+fn level_at(route: &[(Coordinates, u16)], distance: f64) -> u16 {
+    let mut along = 0.0;
+
+    for leg in route.windows(2) {
+        let ((from, from_level), (to, to_level)) = (leg[0], leg[1]);
+        let length = from.distance(to);
+
+        if distance <= along + length {
+            let fraction = if length > 0.0 {
+                (distance - along) / length
+            } else {
+                1.0
+            };
+            let level =
+                f64::from(from_level) + (f64::from(to_level) - f64::from(from_level)) * fraction;
+
+            // The level is between two `u16` levels
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            return level.round() as u16;
+        }
+
+        along += length;
+    }
+
+    route.last().map_or(0, |&(_, level)| level)
+}
+
+/// Returns the tokens following the first occurrence of `pattern`
+fn after<'a, 'b>(tokens: &'b [&'a str], pattern: &[&str]) -> Option<&'b [&'a str]> {
+    let start = tokens
+        .windows(pattern.len())
+        .position(|window| window == pattern)?;
+
+    tokens.get(start + pattern.len()..)
+}
+
+/// Returns the tokens following `pattern`, up to the first token starting with `end`
+fn section<'a, 'b>(tokens: &'b [&'a str], pattern: &[&str], end: &str) -> Option<&'b [&'a str]> {
+    let tokens = after(tokens, pattern)?;
+    let end = tokens
+        .iter()
+        .position(|token| token.starts_with(end))
+        .unwrap_or(tokens.len());
+
+    Some(&tokens[..end])
+}
+
+/// Parses a day and time token, e.g. `08/1850Z` into `081850`
+fn time(tokens: &[&str]) -> Option<String> {
+    let (day, time) = tokens.first()?.trim_end_matches('Z').split_once('/')?;
+
+    (day.len() == 2 && time.len() == 4).then(|| format!("{day}{time}"))
+}
+
+/// Parses a latitude such as `N4530` or `S08`, or a longitude such as `E00315` or `W096`
+fn angle(token: &str, positive: char, negative: char, degrees: usize) -> Option<f64> {
+    let sign = match token.chars().next()? {
+        c if c == positive => 1.0,
+        c if c == negative => -1.0,
+        _ => return None,
+    };
+    let digits = &token[1..];
+
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let value = match digits.len() {
+        len if len == degrees => digits.parse::<f64>().ok()?,
+        len if len == degrees + 2 => {
+            digits[..degrees].parse::<f64>().ok()? + digits[degrees..].parse::<f64>().ok()? / 60.0
+        }
+        _ => return None,
+    };
+
+    Some(sign * value)
+}
+
+/// Parses a position made of a latitude and a longitude token
+fn coordinates(tokens: &[&str]) -> Option<Coordinates> {
+    Some(Coordinates::new(
+        angle(tokens.first()?, 'N', 'S', 2)?,
+        angle(tokens.get(1)?, 'E', 'W', 3)?,
+    ))
+}
+
+/// Parses a radius such as `150NM` or `30KM` into nautical miles
+fn radius(tokens: &[&str]) -> Option<f64> {
+    let token = tokens.first()?;
+    let (value, unit) = token.split_at(token.find(|c: char| !c.is_ascii_digit())?);
+    let value = value.parse::<f64>().ok()?;
+
+    match unit {
+        "NM" => Some(value),
+        "KM" => Some(value / KM_PER_NM),
+        _ => None,
+    }
+}
+
+/// Returns the polygon approximating a circle
+fn circle(centre: Coordinates, radius: f64) -> Vec<Coordinates> {
+    (0..CIRCLE_VERTICES)
+        .map(|i| centre.destination(f64::from(i) * 360.0 / f64::from(CIRCLE_VERTICES), radius))
+        .collect()
+}
+
+/// Decodes the area of a message, see `Hazard::parse`
+fn area(
+    tokens: &[&str],
+    location: &str,
+    geodata: Option<&Geodata>,
+) -> Option<Vec<Vec<Coordinates>>> {
+    if after(tokens, &["ENTIRE", "FIR"]).is_some() {
+        let fir = location.parse::<Fir>().ok()?;
+        let rings = geodata?
+            .boundary(fir)?
+            .polygons
+            .iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        return (!rings.is_empty()).then_some(rings);
+    }
+
+    let start = match after(tokens, &["WI"]) {
+        Some(within) => {
+            if let (Some(radius), Some(["OF", centre @ ..])) = (radius(within), within.get(1..)) {
+                return Some(vec![circle(coordinates(centre)?, radius)]);
+            }
+
+            within
+        }
+        // Volcanic ash clouds are listed without `WI`
+        None => {
+            let first = tokens
+                .iter()
+                .position(|token| angle(token, 'N', 'S', 2).is_some())?;
+
+            &tokens[first..]
+        }
+    };
+
+    let mut points = Vec::new();
+    let mut rest = start;
+
+    while let Some(position) = coordinates(rest) {
+        points.push(position);
+
+        match rest.get(2) {
+            Some(&"-") => rest = &rest[3..],
+            _ => break,
+        }
+    }
+
+    (points.len() >= 3).then_some(vec![points])
+}
+
+/// Parses a flight level token, e.g. `FL060`
+fn flight_level(token: &str) -> Option<u16> {
+    token.strip_prefix("FL")?.parse().ok()
+}
+
+/// Decodes the flight levels of a message, e.g. `SFC/FL060`, `FL250/350`, `TOP FL500`,
+/// `ABV FL100`, `BLW FL100` or `FL300`, `(None, None)` if there are none
+fn levels(tokens: &[&str]) -> (Option<u16>, Option<u16>) {
+    for (i, &token) in tokens.iter().enumerate() {
+        if let Some((base, top)) = token.split_once('/') {
+            let top = top.strip_prefix("FL").unwrap_or(top).parse::<u16>().ok();
+
+            match (base, top) {
+                ("SFC", Some(top)) => return (None, Some(top)),
+                (base, Some(top)) if flight_level(base).is_some() => {
+                    return (flight_level(base), Some(top))
+                }
+                _ => {}
+            }
+        }
+
+        // e.g. TOP ABV FL500
+        let next = tokens[i + 1..]
+            .iter()
+            .find(|token| !matches!(**token, "ABV" | "BLW"))
+            .and_then(|token| flight_level(token));
+
+        match (token, next) {
+            ("TOP" | "BLW", Some(level)) => return (None, Some(level)),
+            ("ABV", Some(level)) => return (Some(level), None),
+            _ => {}
+        }
+
+        if let Some(level) = flight_level(token) {
+            return (Some(level), Some(level));
+        }
+    }
+
+    (None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hazard() {
+        let sigmet = "LFMM SIGMET U05 VALID 231800/232200 LFPW-
+LFMM MARSEILLE FIR/UIR SEV TURB FCST WI N4215 E00315 - N4215 E00230 -
+ N4345 E00245 - N4500 E00415 - N4445 E00545 - N4315 E00515 - N4315
+E00445 - N4430 E00430 - N4315 E00300 - N4215 E00315 SFC/FL060 STNR NC=";
+        let hazard = Hazard::parse(HazardKind::Sigmet, "LFMM", sigmet, None).unwrap();
+        assert_eq!(hazard.area.len(), 1);
+        assert_eq!(hazard.area[0].len(), 10);
+        assert_eq!(hazard.area[0][0], Coordinates::new(42.25, 3.25));
+        assert_eq!((hazard.base, hazard.top), (None, Some(60)));
+        assert_eq!(hazard.valid_from.as_deref(), Some("231800"));
+        assert_eq!(hazard.valid_to.as_deref(), Some("232200"));
+        assert!(hazard.contains(Coordinates::new(44.5, 4.8), 50));
+        assert!(!hazard.contains(Coordinates::new(44.5, 4.8), 100));
+        assert!(!hazard.contains(Coordinates::new(43.6, 1.4), 50));
+
+        // Toulouse to Lyon, climbing to FL050 then FL100
+        let route = [
+            (Coordinates::new(43.63, 1.36), 0),
+            (Coordinates::new(44.2, 3.0), 50),
+            (Coordinates::new(44.8, 4.8), 50),
+            (Coordinates::new(45.73, 5.08), 100),
+        ];
+        let intersections = Intersection::find(&route, std::slice::from_ref(&hazard));
+        assert!(!intersections.is_empty());
+        assert!(intersections
+            .windows(2)
+            .all(|pair| pair[0].exit_distance < pair[1].entry_distance));
+        let first = &intersections[0];
+        assert!(first.entry_distance > 0.0 && first.entry_distance <= first.exit_distance);
+        assert!(hazard.contains(first.entry, 50));
+        assert!(Intersection::find(&[(route[0].0, 100), (route[3].0, 100)], &[hazard]).is_empty());
+
+        let airmet = "LFFF AIRMET 1 VALID 101000/101400 LFPW- LFFF PARIS FIR MOD ICE \
+            OBS ENTIRE FIR FL040/080 STNR NC=";
+        let geodata = Geodata::fixture();
+        let hazard = Hazard::parse(HazardKind::Airmet, "LFFF", airmet, Some(&geodata)).unwrap();
+        assert_eq!(
+            hazard.area,
+            geodata.boundary(Fir::LFFF).unwrap().polygons[0]
+        );
+        assert_eq!((hazard.base, hazard.top), (Some(40), Some(80)));
+        assert!(Hazard::parse(HazardKind::Airmet, "LFFF", airmet, None).is_none());
+
+        // Every polygon of the FIR is part of the area, e.g. Corsica for LFMM
+        let sigmet = "LFMM SIGMET 2 VALID 101000/101400 LFPW- LFMM MARSEILLE FIR/UIR SEV TURB \
+            OBS ENTIRE FIR FL200/300 STNR NC=";
+        let hazard = Hazard::parse(HazardKind::Sigmet, "LFMM", sigmet, Some(&geodata)).unwrap();
+        assert_eq!(hazard.area.len(), 2);
+        assert!(hazard.contains(Coordinates::new(44.0, 5.0), 250));
+        assert!(hazard.contains(Coordinates::new(42.0, 9.0), 250));
+        assert!(!hazard.contains(Coordinates::new(42.0, 10.5), 250));
+
+        // Bastia to Pisa
+        let route = [
+            (Coordinates::new(42.55, 9.48), 250),
+            (Coordinates::new(43.68, 10.39), 250),
+        ];
+        let intersections = Intersection::find(&route, std::slice::from_ref(&hazard));
+        assert_eq!(intersections.len(), 1);
+        assert_eq!(intersections[0].entry_distance, 0.0);
+        assert!(intersections[0].exit_distance > 0.0);
+
+        let vaa = "VA ADVISORY
+DTG: 20240708/1910Z
+VAAC: DARWIN
+ERUPTION DETAILS: VA TO FL100 OBS AT 08/1850Z MOV WNW
+OBS VA DTG: 08/1850Z
+OBS VA CLD: SFC/FL100 S0835 E12249 - S0831 E12137 - S0817
+        E12127 - S0812 E12143 - S0830 E12249 MOV W 15KT
+FCST VA CLD +6 HR: 09/0050Z SFC/FL100 S0835 E12249 - S0835
+        E12101 - S0759 E12038 - S0752 E12129 - S0830 E12249
+NXT ADVISORY: NO LATER THAN 20240709/0110Z=";
+        let hazard = Hazard::parse(HazardKind::VolcanicAsh, "ADRM", vaa, None).unwrap();
+        assert_eq!(hazard.area[0].len(), 5);
+        assert_eq!((hazard.base, hazard.top), (None, Some(100)));
+        assert_eq!(hazard.valid_from.as_deref(), Some("081850"));
+        assert_eq!(hazard.valid_to.as_deref(), Some("090050"));
+
+        let tca = "TC ADVISORY
+DTG: 20240708/1200Z
+TCAC: MIAMI
+TC: BERYL
+OBS PSN: 08/1200Z N2830 W09600
+CB: WI 150NM OF TC CENTRE TOP FL500
+MOV: NNE 10KT
+FCST PSN +6 HR: 08/1800Z N2948 W09554=";
+        let hazard = Hazard::parse(HazardKind::TropicalCyclone, "KNHC", tca, None).unwrap();
+        assert_eq!(hazard.area[0].len(), 36);
+        assert_eq!((hazard.base, hazard.top), (None, Some(500)));
+        assert_eq!(hazard.valid_from.as_deref(), Some("081200"));
+        assert_eq!(hazard.valid_to.as_deref(), Some("081800"));
+        assert!(hazard.contains(Coordinates::new(28.5, -95.0), 300));
+        assert!(!hazard.contains(Coordinates::new(28.5, -92.0), 300));
+
+        assert!(Hazard::parse(HazardKind::Sigmet, "LFMM", "NIL", None).is_none());
+        assert!(
+            Hazard::parse(HazardKind::Sigmet, "LFMM", "SEV TURB N OF N45 FL300", None).is_none()
+        );
+    }
+}
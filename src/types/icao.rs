@@ -0,0 +1,92 @@
+use crate::airport::Airport;
+use crate::error::Error;
+use crate::fir::Fir;
+use crate::helpers::serde_from_str;
+
+/// ICAO location indicator of any station, FIR or producing center, e.g. EGLL, KJFK, GCXO.
+/// It's made of 4 letters, stored in uppercase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Icao(String);
+
+impl Icao {
+    /// # Errors
+    ///
+    /// Returns an error if the code is not made of 4 ASCII letters.
+    ///
+    pub fn new(code: &str) -> Result<Icao, Error> {
+        if code.len() != 4 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(Error::InvalidIcao(code.to_string()));
+        }
+
+        Ok(Icao(code.to_ascii_uppercase()))
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for Icao {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Icao::new(s)
+    }
+}
+
+impl TryFrom<&str> for Icao {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Icao::new(value)
+    }
+}
+
+impl std::fmt::Display for Icao {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Icao {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<Airport> for Icao {
+    fn from(value: Airport) -> Self {
+        Icao(value.to_string())
+    }
+}
+
+impl From<Fir> for Icao {
+    fn from(value: Fir) -> Self {
+        Icao(value.to_string())
+    }
+}
+
+serde_from_str!(Icao);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icao() {
+        assert_eq!(Icao::new("EGLL").unwrap().as_str(), "EGLL");
+        assert_eq!("kjfk".parse::<Icao>().unwrap().to_string(), "KJFK");
+        assert_eq!(Icao::from(Airport::LFBO), Icao::new("LFBO").unwrap());
+
+        for code in ["", "EGL", "EGLLL", "EG1L", "ÉGLL"] {
+            assert!(matches!(Icao::new(code), Err(Error::InvalidIcao(_))));
+        }
+
+        assert_eq!(
+            serde_json::from_str::<Icao>("\"gcxo\"").unwrap(),
+            Icao::new("GCXO").unwrap()
+        );
+        assert!(serde_json::from_str::<Icao>("\"GCX\"").is_err());
+    }
+}
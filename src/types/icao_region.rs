@@ -0,0 +1,45 @@
+use crate::helpers::serde_from_str;
+
+/// ICAO air navigation region, e.g. EUR, NAT.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    strum::Display,
+    strum::EnumIter,
+    strum::EnumString,
+)]
+#[strum(ascii_case_insensitive)]
+pub enum IcaoRegion {
+    /// Africa-Indian Ocean
+    #[strum(serialize = "AFI")]
+    Afi,
+    /// Asia and Pacific
+    #[strum(serialize = "APAC")]
+    AsiaPacific,
+    /// Caribbean and Central America
+    #[strum(serialize = "CAR")]
+    Caribbean,
+    /// Europe, including Russia and Central Asia
+    #[strum(serialize = "EUR")]
+    Europe,
+    /// Middle East
+    #[strum(serialize = "MID")]
+    MiddleEast,
+    /// North America
+    #[strum(serialize = "NAM")]
+    NorthAmerica,
+    /// North Atlantic
+    #[strum(serialize = "NAT")]
+    NorthAtlantic,
+    /// South America
+    #[strum(serialize = "SAM")]
+    SouthAmerica,
+}
+
+serde_from_str!(IcaoRegion);
@@ -0,0 +1,55 @@
+use crate::helpers::XmlWriter;
+use crate::message::Message;
+use crate::report::Report;
+use serde::{Deserialize, Serialize};
+
+/// Messages of an airport or a producing center
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    /// e.g. LFBO, LFBA
+    #[serde(alias = "@oaci")]
+    pub oaci: String,
+
+    /// e.g. TOULOUSE BLAGNAC, AGEN LA GARENNE
+    #[serde(alias = "@nom")]
+    pub name: String,
+
+    /// Messages in the order sent by the server, with a single message without text (NIL) when
+    /// there is none
+    #[serde(default, alias = "message")]
+    pub messages: Vec<Message>,
+}
+
+impl Location {
+    /// Writes the location and its messages as a `messages` element
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter) -> std::io::Result<()> {
+        writer
+            .create_element("messages")
+            .with_attribute(("oaci", self.oaci.as_str()))
+            .with_attribute(("nom", self.name.as_str()))
+            .write_inner_content(|writer| {
+                self.messages
+                    .iter()
+                    .try_for_each(|message| message.write_xml(writer))
+            })?;
+
+        Ok(())
+    }
+}
+
+impl Report for Location {
+    fn oaci(&self) -> &str {
+        &self.oaci
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn messages(&self) -> impl Iterator<Item = Message> + '_ {
+        self.messages
+            .iter()
+            .filter(|message| message.text.is_some())
+            .cloned()
+    }
+}
@@ -1,14 +1,15 @@
-use crate::helpers::de_option_link;
-use serde::Deserialize;
+use crate::helpers::{de_option_link, link_path, write_cdata, XmlWriter};
+use quick_xml::events::BytesText;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Map {
     /// e.g. WINTEM, TEMSI
     #[serde(alias = "typecarte", alias = "type")]
     pub category: String,
 
     /// e.g. FL20-100, FL50
-    #[serde(rename = "niveau")]
+    #[serde(alias = "niveau")]
     pub level: String,
 
     /// e.g. EUR, ANTILLES
@@ -16,18 +17,55 @@ pub struct Map {
     pub zone: String,
 
     /// e.g. 24 04 2024 12:00
-    #[serde(rename = "date_run")]
+    #[serde(alias = "date_run")]
     pub run_date: String,
 
     /// e.g. 24 04 2024 00:00
-    #[serde(rename = "date_echeance")]
+    #[serde(alias = "date_echeance")]
     pub due_date: String,
 
     /// e.g. 06 UTC
-    #[serde(rename = "echeance")]
+    #[serde(alias = "echeance")]
     pub due_hour: String,
 
     /// e.g. <https://aviation.meteo.fr/...>
-    #[serde(rename = "lien", deserialize_with = "de_option_link")]
+    #[serde(alias = "lien", deserialize_with = "de_option_link")]
     pub link: Option<String>,
 }
+
+impl Map {
+    /// Writes the map as a `carte` element. The category and zone elements are named `typecarte`
+    /// and `zone` in flight plans, `type` and `zone_carte` in lists of maps.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter,
+        flight_plan: bool,
+    ) -> std::io::Result<()> {
+        let (category, zone) = if flight_plan {
+            ("typecarte", "zone")
+        } else {
+            ("type", "zone_carte")
+        };
+
+        writer
+            .create_element("carte")
+            .write_inner_content(|writer| {
+                for (name, value) in [
+                    (category, &self.category),
+                    ("niveau", &self.level),
+                    (zone, &self.zone),
+                    ("date_run", &self.run_date),
+                    ("date_echeance", &self.due_date),
+                    ("echeance", &self.due_hour),
+                ] {
+                    writer
+                        .create_element(name)
+                        .write_text_content(BytesText::new(value))?;
+                }
+
+                write_cdata(writer, "lien", Some(link_path(self.link.as_deref())), "NIL")
+            })?;
+
+        Ok(())
+    }
+}
@@ -1,17 +1,36 @@
-use crate::helpers::de_option_string;
-use serde::Deserialize;
+use crate::helpers::{de_option_string, write_cdata, XmlWriter};
+use crate::message_category::MessageCategory;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     /// e.g. TCA
-    #[serde(rename = "@type")]
-    pub category: String,
+    #[serde(alias = "@type")]
+    pub category: MessageCategory,
 
     /// e.g. 20240620210000
-    #[serde(rename = "@date_reception", deserialize_with = "de_option_string")]
+    #[serde(alias = "@date_reception", deserialize_with = "de_option_string")]
     pub reception_date: Option<String>,
 
     /// e.g. TC ADVISORY ...
     #[serde(default, deserialize_with = "de_option_string", alias = "texte")]
     pub text: Option<String>,
 }
+
+impl Message {
+    /// Writes the message as a `message` element
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter) -> std::io::Result<()> {
+        writer
+            .create_element("message")
+            .with_attribute(("type", self.category.to_string().as_str()))
+            .with_attribute((
+                "date_reception",
+                self.reception_date.as_deref().unwrap_or("NIL"),
+            ))
+            .write_inner_content(|writer| {
+                write_cdata(writer, "texte", self.text.as_deref(), "NIL")
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+/// Type of a message, as sent by the server, e.g. METAR, TAFL.
+/// Categories unknown to this library are kept in `MessageCategory::Other`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, strum::Display, strum::EnumString,
+)]
+#[serde(from = "String", into = "String")]
+#[non_exhaustive]
+pub enum MessageCategory {
+    /// Routine aerodrome weather report, issued every 30 or 60 minutes
+    #[strum(serialize = "METAR")]
+    Metar,
+    /// Special aerodrome weather report, issued when the conditions change significantly
+    #[strum(serialize = "SPECI")]
+    Speci,
+    /// Aerodrome forecast
+    #[strum(serialize = "TAF")]
+    Taf,
+    /// Long aerodrome forecast, valid for 24 to 30 hours
+    #[strum(serialize = "TAFL")]
+    Tafl,
+    /// Short aerodrome forecast, valid for 9 to 12 hours
+    #[strum(serialize = "TAFC")]
+    Tafc,
+    /// Significant meteorological information, hazardous weather for all aircraft in a FIR
+    #[strum(serialize = "SIGMET")]
+    Sigmet,
+    /// Airmen's meteorological information, hazardous weather for low-level flights in a FIR
+    #[strum(serialize = "AIRMET")]
+    Airmet,
+    /// Area forecast for low-level flights in a FIR
+    #[strum(serialize = "GAMET")]
+    Gamet,
+    /// Aerodrome warning (Message d'Avertissement d'Aérodrome), e.g. thunderstorms, strong winds
+    #[strum(serialize = "MAA")]
+    Maa,
+    /// Take-off forecast (PREvision DECollage) of wind, temperature and pressure
+    #[strum(serialize = "PREDEC")]
+    Predec,
+    /// Tropical cyclone advisory
+    #[strum(serialize = "TCA")]
+    Tca,
+    /// Volcanic ash advisory
+    #[strum(serialize = "VAA")]
+    Vaa,
+    /// Space weather advisory
+    #[strum(serialize = "SWX")]
+    Swx,
+    /// Category unknown to this library, e.g. a new product of the server
+    #[strum(default)]
+    Other(String),
+}
+
+impl From<String> for MessageCategory {
+    fn from(value: String) -> Self {
+        // Cannot fail thanks to `MessageCategory::Other`
+        value.parse().unwrap_or(MessageCategory::Other(value))
+    }
+}
+
+impl From<MessageCategory> for String {
+    fn from(value: MessageCategory) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_category() {
+        assert_eq!(
+            MessageCategory::from(String::from("TAFL")),
+            MessageCategory::Tafl
+        );
+        assert_eq!(MessageCategory::Tafl.to_string(), "TAFL");
+
+        let category = MessageCategory::from(String::from("WAFS"));
+        assert_eq!(category, MessageCategory::Other(String::from("WAFS")));
+        assert_eq!(category.to_string(), "WAFS");
+    }
+}
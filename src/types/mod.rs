@@ -1,8 +1,19 @@
 pub mod airport;
+pub mod airport_info;
 pub mod center;
+pub mod coordinates;
+pub mod diff;
 pub mod error;
 pub mod fir;
+pub mod fir_boundary;
+pub mod fir_info;
+pub mod geodata;
+pub mod hazard;
+pub mod icao;
+pub mod icao_region;
+pub mod location;
 pub mod map;
 pub mod message;
-pub mod oaci;
-pub mod oaci_multiple;
+pub mod message_category;
+pub mod product;
+pub mod report;
@@ -0,0 +1,37 @@
+/// Products served by the API, displayed as their `TYPE_DONNEES` value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::Display, strum::EnumIter)]
+pub enum Product {
+    /// Pre-established flight plans
+    #[strum(serialize = "DOSSIER")]
+    FlightPlan,
+    /// Aerodrome warnings
+    #[strum(serialize = "MAA")]
+    Maa,
+    /// TEMSI and WINTEM maps
+    #[strum(serialize = "CARTES")]
+    Maps,
+    /// METAR, SPECI, TAF, SIGMET, ...
+    #[strum(serialize = "OPMET2")]
+    Opmet,
+    /// Take-off forecasts
+    #[strum(serialize = "PREDEC")]
+    Predec,
+    /// SIGMET, AIRMET and GAMET
+    #[strum(serialize = "SIGMET2")]
+    Sigmet,
+    /// Space weather advisories
+    #[strum(serialize = "SW")]
+    SpaceWeather,
+    /// Tropical cyclone advisories
+    #[strum(serialize = "TCA")]
+    Tca,
+    /// Tropical cyclone advisory graphics
+    #[strum(serialize = "TCAG")]
+    Tcag,
+    /// Volcanic ash advisories
+    #[strum(serialize = "VAA")]
+    Vaa,
+    /// Volcanic ash advisory graphics
+    #[strum(serialize = "VAG")]
+    Vag,
+}
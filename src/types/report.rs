@@ -0,0 +1,34 @@
+use crate::message::Message;
+
+/// Messages of a location (airport, FIR, producing center), whatever the product they come from
+pub trait Report {
+    /// e.g. LFBO, LFMM, KWNP
+    fn oaci(&self) -> &str;
+
+    /// e.g. TOULOUSE BLAGNAC, MARSEILLE, NOAA/SWPC
+    fn name(&self) -> &str;
+
+    /// Messages of the location in the order sent by the server, without the missing ones (NIL,
+    /// NODATA)
+    fn messages(&self) -> impl Iterator<Item = Message> + '_;
+
+    /// Returns the most recently received message, or the first one if their reception dates are
+    /// unknown
+    fn latest_message(&self) -> Option<Message> {
+        self.messages().reduce(|latest, message| {
+            if message.reception_date > latest.reception_date {
+                message
+            } else {
+                latest
+            }
+        })
+    }
+}
+
+/// Implemented by the responses made of one report per location, i.e. all of them but the maps
+/// and graphics (`Maps`, `Tcag`, `Vag`)
+pub trait Response {
+    type Report: Report;
+
+    fn reports(&self) -> &[Self::Report];
+}
@@ -0,0 +1,575 @@
+use crate::airport::Airport;
+use crate::client::Client;
+use crate::error::Error;
+use crate::fir::Fir;
+use crate::geodata::Geodata;
+use crate::icao::Icao;
+use crate::message::Message;
+use crate::message_category::MessageCategory;
+use crate::product::Product;
+use crate::report::{Report, Response};
+use futures::future::try_join_all;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Maximum number of locations per request
+const LIMIT: usize = 50;
+
+/// Products which can be watched
+const WATCHED: [Product; 3] = [Product::Opmet, Product::Maa, Product::Sigmet];
+
+#[derive(Debug, Clone)]
+pub struct RequestOptions {
+    /// List of OACI codes of the airports
+    /// e.g. `Airport::LFBO`, `Airport::LFBA`
+    pub airports: Vec<Airport>,
+
+    /// Other ICAO location indicators, e.g. `EGLL`, `KJFK`, only watched with OPMET
+    pub locations: Vec<Icao>,
+
+    /// FIRs watched with SIGMET, along with the FIRs of the airports
+    pub firs: Vec<Fir>,
+
+    /// Products polled along with their polling interval. Only `Product::Opmet`, `Product::Maa`
+    /// and `Product::Sigmet` can be watched.
+    /// Default is every 5 minutes for OPMET and MAA, every 10 minutes for SIGMET.
+    pub products: Vec<(Product, Duration)>,
+
+    /// If `true`, the messages in force at the first poll of a product are emitted as new ones.
+    /// Default is `true`.
+    pub initial: bool,
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        RequestOptions {
+            airports: vec![],
+            locations: vec![],
+            firs: vec![],
+            products: vec![
+                (Product::Opmet, Duration::from_secs(5 * 60)),
+                (Product::Maa, Duration::from_secs(5 * 60)),
+                (Product::Sigmet, Duration::from_secs(10 * 60)),
+            ],
+            initial: true,
+        }
+    }
+}
+
+impl RequestOptions {
+    /// Checks the options before the first poll
+    fn validate(&self) -> Result<(), Error> {
+        if self.airports.is_empty() && self.locations.is_empty() && self.firs.is_empty() {
+            return Err(Error::InvalidOptions(
+                "RequestOptions.airports, RequestOptions.locations and RequestOptions.firs must be at least 1 combined".to_string(),
+            ));
+        }
+
+        if self.products.is_empty() {
+            return Err(Error::InvalidOptions(
+                "RequestOptions.products must not be empty".to_string(),
+            ));
+        }
+
+        for (product, interval) in &self.products {
+            if !WATCHED.contains(product) {
+                return Err(Error::InvalidOptions(format!(
+                    "{product} cannot be watched, only OPMET2, MAA and SIGMET2 can"
+                )));
+            }
+
+            if interval.is_zero() {
+                return Err(Error::InvalidOptions(format!(
+                    "The polling interval of {product} must not be zero"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+pub enum ChangeKind {
+    /// A message appeared, e.g. a new METAR or SPECI
+    #[strum(serialize = "NEW")]
+    New,
+
+    /// A message amending or correcting a previous one appeared, e.g. TAF AMD, METAR COR
+    #[strum(serialize = "AMENDED")]
+    Amended,
+
+    /// A message cancelling a previous one appeared, e.g. CNL SIGMET
+    #[strum(serialize = "CANCELLED")]
+    Cancelled,
+
+    /// A message is no longer sent by the server, without any new message of its category for
+    /// the location
+    #[strum(serialize = "EXPIRED")]
+    Expired,
+}
+
+/// Change of the messages of a location between two polls of a product
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+
+    /// Product the message comes from
+    pub product: Product,
+
+    /// e.g. LFBO, LFMM
+    pub location: String,
+
+    /// The new message, or the expired one
+    pub message: Message,
+}
+
+impl ChangeKind {
+    /// Returns the kind of change of a message which was not sent by the previous poll
+    fn of(message: &Message) -> ChangeKind {
+        let words = message
+            .text
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>();
+
+        if words.contains(&"CNL") {
+            ChangeKind::Cancelled
+        } else if words.contains(&"AMD") || words.contains(&"COR") {
+            ChangeKind::Amended
+        } else {
+            ChangeKind::New
+        }
+    }
+}
+
+/// Messages sent by the previous poll of each product, per location and category
+#[derive(Debug, Default)]
+struct Seen {
+    messages: HashMap<(Product, String, MessageCategory), Vec<Message>>,
+    polled: HashSet<Product>,
+}
+
+impl Seen {
+    /// Records the messages of a poll and returns the changes since the previous one. Messages
+    /// are the same if their category, reception date and text are.
+    fn update(
+        &mut self,
+        product: Product,
+        reports: Vec<(String, Vec<Message>)>,
+        initial: bool,
+    ) -> Vec<Change> {
+        let first = self.polled.insert(product);
+        let mut current = HashMap::<(Product, String, MessageCategory), Vec<Message>>::new();
+        let mut changes = Vec::new();
+
+        for (location, messages) in reports {
+            for message in messages {
+                let key = (product, location.clone(), message.category.clone());
+                let messages = current.entry(key.clone()).or_default();
+
+                if messages.contains(&message) {
+                    continue;
+                }
+
+                let previous = self.messages.get(&key).map(Vec::as_slice);
+                if (initial || !first) && !previous.unwrap_or_default().contains(&message) {
+                    changes.push(Change {
+                        kind: ChangeKind::of(&message),
+                        product,
+                        location: location.clone(),
+                        message: message.clone(),
+                    });
+                }
+
+                messages.push(message);
+            }
+        }
+
+        let mut expired = Vec::new();
+
+        for (key, previous) in self.messages.iter().filter(|(key, _)| key.0 == product) {
+            let messages = current.get(key).map(Vec::as_slice).unwrap_or_default();
+
+            // A report replaced by a new one of its category is not expired, e.g. a METAR, unlike
+            // the SIGMETs and aerodrome warnings which are in force side by side
+            if replaced(&key.2) && messages.iter().any(|message| !previous.contains(message)) {
+                continue;
+            }
+
+            for message in previous
+                .iter()
+                .filter(|message| !messages.contains(message))
+            {
+                expired.push(Change {
+                    kind: ChangeKind::Expired,
+                    product,
+                    location: key.1.clone(),
+                    message: message.clone(),
+                });
+            }
+        }
+
+        expired.sort_by(|a, b| {
+            (&a.location, a.message.category.to_string())
+                .cmp(&(&b.location, b.message.category.to_string()))
+        });
+        changes.extend(expired);
+
+        self.messages.retain(|key, _| key.0 != product);
+        self.messages.extend(current);
+
+        changes
+    }
+}
+
+/// Returns `true` for the categories whose latest message replaces the previous ones
+fn replaced(category: &MessageCategory) -> bool {
+    matches!(
+        category,
+        MessageCategory::Metar
+            | MessageCategory::Speci
+            | MessageCategory::Taf
+            | MessageCategory::Tafl
+            | MessageCategory::Tafc
+    )
+}
+
+/// State of the stream returned by `Client::watch`
+struct Watch<'a> {
+    client: &'a Client,
+    options: RequestOptions,
+    seen: Seen,
+
+    /// FIRs polled with SIGMET, those of the options followed by those of the airports
+    firs: Vec<Fir>,
+
+    /// Next poll of each product
+    schedule: Vec<(Product, Duration, Instant)>,
+
+    /// Changes and errors waiting to be emitted
+    pending: VecDeque<Result<Change, Error>>,
+}
+
+impl Watch<'_> {
+    /// Waits for the next product to poll, then retrieves it and records its changes
+    async fn poll_next(&mut self) {
+        let Some((product, interval, next)) =
+            self.schedule.iter_mut().min_by_key(|(_, _, next)| *next)
+        else {
+            return;
+        };
+
+        tokio::time::sleep_until(*next).await;
+        *next = Instant::now() + *interval;
+        let product = *product;
+
+        match self.fetch(product).await {
+            Ok(reports) => self.pending.extend(
+                self.seen
+                    .update(product, reports, self.options.initial)
+                    .into_iter()
+                    .map(Ok),
+            ),
+            // The previous messages are kept, the changes are emitted by the next successful poll
+            Err(e) => self.pending.push_back(Err(e)),
+        }
+    }
+
+    /// Retrieves the messages of a product, per location
+    async fn fetch(&self, product: Product) -> Result<Vec<(String, Vec<Message>)>, Error> {
+        let options = &self.options;
+
+        match product {
+            Product::Opmet => {
+                let mut locations = Vec::<Icao>::new();
+
+                for location in options
+                    .airports
+                    .iter()
+                    .map(|&airport| Icao::from(airport))
+                    .chain(options.locations.iter().cloned())
+                {
+                    if !locations.contains(&location) {
+                        locations.push(location);
+                    }
+                }
+
+                let responses = try_join_all(locations.chunks(LIMIT).map(|locations| {
+                    self.client.get_opmet(crate::opmet::RequestOptions {
+                        locations: locations.to_vec(),
+                        ..Default::default()
+                    })
+                }))
+                .await?;
+
+                Ok(messages(&responses))
+            }
+            Product::Maa => {
+                let responses = try_join_all(options.airports.chunks(LIMIT).map(|airports| {
+                    self.client.get_maa(crate::maa::RequestOptions {
+                        airports: airports.to_vec(),
+                        ..Default::default()
+                    })
+                }))
+                .await?;
+
+                Ok(messages(&responses))
+            }
+            Product::Sigmet => {
+                let responses = try_join_all(
+                    crate::sigmet::RequestOptions::for_firs(&self.firs)
+                        .into_iter()
+                        .map(|options| self.client.get_sigmet(options)),
+                )
+                .await?;
+
+                Ok(messages(&responses))
+            }
+            _ => Err(Error::InvalidOptions(format!(
+                "{product} cannot be watched"
+            ))),
+        }
+    }
+}
+
+/// Returns the messages of the responses, per location
+fn messages<R: Response>(responses: &[R]) -> Vec<(String, Vec<Message>)> {
+    responses
+        .iter()
+        .flat_map(Response::reports)
+        .map(|report| (report.oaci().to_string(), report.messages().collect()))
+        .collect()
+}
+
+/// Polls the products of the options forever, see `Client::watch`
+pub(crate) fn watch<'a>(
+    client: &'a Client,
+    options: RequestOptions,
+    geodata: &Geodata,
+) -> Result<BoxStream<'a, Result<Change, Error>>, Error> {
+    options.validate()?;
+
+    let mut firs = options.firs.clone();
+
+    if options
+        .products
+        .iter()
+        .any(|(product, _)| *product == Product::Sigmet)
+    {
+        for &airport in &options.airports {
+            firs.push(geodata.require_fir(airport)?);
+        }
+    }
+
+    let now = Instant::now();
+    let schedule = options
+        .products
+        .iter()
+        .map(|&(product, interval)| (product, interval, now))
+        .collect();
+
+    let watch = Watch {
+        client,
+        options,
+        seen: Seen::default(),
+        firs,
+        schedule,
+        pending: VecDeque::new(),
+    };
+
+    Ok(stream::unfold(watch, |mut watch| async move {
+        loop {
+            if let Some(item) = watch.pending.pop_front() {
+                return Some((item, watch));
+            }
+
+            watch.poll_next().await;
+        }
+    })
+    .boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Transport;
+    use futures::future::{BoxFuture, FutureExt};
+    use std::sync::Mutex;
+
+    /// Serves the responses of each product in turn, the last one being repeated
+    #[derive(Debug, Default)]
+    struct ScriptedTransport {
+        responses: Mutex<HashMap<Product, VecDeque<String>>>,
+    }
+
+    impl Transport for ScriptedTransport {
+        fn fetch<'a>(
+            &'a self,
+            product: Product,
+            _params: &'a str,
+        ) -> BoxFuture<'a, Result<String, Error>> {
+            let mut responses = self.responses.lock().unwrap();
+            let responses = responses.get_mut(&product).unwrap();
+            let body = if responses.len() > 1 {
+                responses.pop_front().unwrap()
+            } else {
+                responses[0].clone()
+            };
+
+            async move { Ok(body) }.boxed()
+        }
+    }
+
+    fn opmet(metar: &str, taf: &str) -> String {
+        format!(
+            r#"<root><opmet oaci="LFBO" nom="TOULOUSE BLAGNAC"><METAR><![CDATA[{metar}]]></METAR><TAF><![CDATA[{taf}]]></TAF><SPECI><![CDATA[NODATA]]></SPECI><SIGMET><![CDATA[NODATA]]></SIGMET><GAMET><![CDATA[NODATA]]></GAMET><AIRMET><![CDATA[NODATA]]></AIRMET></opmet></root>"#
+        )
+    }
+
+    fn maa(texts: &[&str]) -> String {
+        let messages = texts
+            .iter()
+            .map(|text| {
+                format!(r#"<message type="MAA" date_reception="20240715104200"><texte><![CDATA[{text}]]></texte></message>"#)
+            })
+            .collect::<String>();
+
+        format!(
+            r#"<groupe><messages oaci="LFPG" nom="PARIS CHARLES DE GAULLE">{messages}</messages></groupe>"#
+        )
+    }
+
+    #[test]
+    fn test_seen() {
+        let sigmet = |number: u8| Message {
+            category: MessageCategory::Sigmet,
+            reception_date: Some(format!("2024071510{number}000")),
+            text: Some(format!(
+                "LFMM SIGMET {number} VALID 151000/151400 LFPW- LFMM MARSEILLE FIR SEV TURB="
+            )),
+        };
+        let changes = |changes: Vec<Change>| {
+            changes
+                .into_iter()
+                .map(|change| (change.kind, change.message))
+                .collect::<Vec<_>>()
+        };
+
+        let mut seen = Seen::default();
+        let reports = vec![(String::from("LFMM"), vec![sigmet(1), sigmet(2)])];
+        assert_eq!(changes(seen.update(Product::Sigmet, reports, false)), []);
+
+        // SIGMET 1 drops out as SIGMET 3 is issued, both being in force side by side
+        let reports = vec![(String::from("LFMM"), vec![sigmet(2), sigmet(3)])];
+        assert_eq!(
+            changes(seen.update(Product::Sigmet, reports, false)),
+            [
+                (ChangeKind::New, sigmet(3)),
+                (ChangeKind::Expired, sigmet(1))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch() {
+        let warning = "LFPG AD WRNG 1 VALID 151130/152030 TS FCST.=";
+        let cancel = "LFPG AD WRNG 2 VALID 151300/152030 CNL AD WRNG 1 151130/152030=";
+
+        let transport = ScriptedTransport::default();
+        transport.responses.lock().unwrap().extend([
+            (
+                Product::Opmet,
+                VecDeque::from([
+                    opmet("METAR LFBO 151100Z 31008KT CAVOK 25/12 Q1019=", "TAF LFBO 151100Z"),
+                    opmet("METAR LFBO 151130Z 31010KT CAVOK 26/12 Q1019=", "TAF AMD LFBO 151120Z"),
+                ]),
+            ),
+            (
+                Product::Maa,
+                VecDeque::from([
+                    maa(&[warning]),
+                    String::from("<code>NOK</code>"),
+                    maa(&[warning, cancel]),
+                    String::from(r#"<groupe><messages oaci="LFPG" nom="PARIS CHARLES DE GAULLE"><message type="MAA" date_reception="NIL"><texte><![CDATA[NIL]]></texte></message></messages></groupe>"#),
+                ]),
+            ),
+        ]);
+        let client = Client::with_transport(transport);
+
+        let options = RequestOptions {
+            airports: vec![Airport::LFBO],
+            products: vec![
+                (Product::Opmet, Duration::from_millis(10)),
+                (Product::Maa, Duration::from_millis(15)),
+            ],
+            ..Default::default()
+        };
+        let items = client
+            .watch(options, &Geodata::new())
+            .unwrap()
+            .take(9)
+            .collect::<Vec<_>>()
+            .await;
+
+        let changes = |product| {
+            items
+                .iter()
+                .filter_map(|item| item.as_ref().ok())
+                .filter(|change| change.product == product)
+                .map(|change| (change.kind, change.message.text.clone().unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            changes(Product::Opmet),
+            [
+                (
+                    ChangeKind::New,
+                    String::from("METAR LFBO 151100Z 31008KT CAVOK 25/12 Q1019=")
+                ),
+                (ChangeKind::New, String::from("TAF LFBO 151100Z")),
+                (
+                    ChangeKind::New,
+                    String::from("METAR LFBO 151130Z 31010KT CAVOK 26/12 Q1019=")
+                ),
+                (ChangeKind::Amended, String::from("TAF AMD LFBO 151120Z")),
+            ]
+        );
+        assert_eq!(
+            changes(Product::Maa),
+            [
+                (ChangeKind::New, String::from(warning)),
+                (ChangeKind::Cancelled, String::from(cancel)),
+                (ChangeKind::Expired, String::from(warning)),
+                (ChangeKind::Expired, String::from(cancel)),
+            ]
+        );
+        assert!(items
+            .iter()
+            .any(|item| matches!(item, Err(Error::InvalidApiKey))));
+
+        let options = RequestOptions {
+            airports: vec![Airport::LFBO],
+            products: vec![(Product::Maps, Duration::from_secs(60))],
+            ..Default::default()
+        };
+        assert!(matches!(
+            client.watch(options, &Geodata::new()),
+            Err(Error::InvalidOptions(_))
+        ));
+
+        // The FIRs of the airports are needed to watch SIGMET
+        let options = RequestOptions {
+            airports: vec![Airport::LFBO],
+            ..Default::default()
+        };
+        assert!(matches!(
+            client.watch(options, &Geodata::new()),
+            Err(Error::InvalidGeodata(_))
+        ));
+    }
+}